@@ -0,0 +1,254 @@
+//! Checking of `expect-*` annotations against generated remarks.
+use std::io::Write;
+
+use colored::Colorize;
+
+use crate::annotation::{Annotation, Directive};
+use crate::remark::write_remark;
+use crate::remark::{Remark, RemarkType};
+
+/// Expectation that was not fulfilled by the generated remarks.
+pub struct BrokenExpectation<'a> {
+    pub annotation: &'a Annotation,
+    /// Remarks that have broken the expectation.
+    pub remarks: Vec<&'a Remark>,
+    pub reason: String,
+}
+
+/// Checks `expect-*` annotations against the remarks.
+/// Annotations that are not expectations are ignored.
+pub fn check_expectations<'a>(
+    annotations: &'a [Annotation],
+    remarks: &'a [Remark],
+) -> Vec<BrokenExpectation<'a>> {
+    annotations
+        .iter()
+        .filter(|annotation| annotation.directive.is_expectation())
+        .filter_map(|annotation| check_expectation(annotation, remarks))
+        .collect()
+}
+
+fn check_expectation<'a>(
+    annotation: &'a Annotation,
+    remarks: &'a [Remark],
+) -> Option<BrokenExpectation<'a>> {
+    let located = || {
+        remarks
+            .iter()
+            .filter(|remark| annotation.is_located_in(remark))
+    };
+    // Passed remarks are only used to check that an optimization has happened
+    let located_missed = || located().filter(|remark| remark.remark_type == RemarkType::Missed);
+    let matches_kind = |remark: &Remark, kind: &str| remark.name == kind || remark.pass == kind;
+
+    let (remarks, reason): (Vec<&Remark>, String) = match &annotation.directive {
+        Directive::ExpectVectorized => {
+            let loops = located().filter(|remark| remark.pass == "loop-vectorize");
+            return check_optimized(
+                annotation,
+                loops,
+                "a loop was not vectorized",
+                "no vectorized loop was found",
+            );
+        }
+        Directive::ExpectInlined => {
+            let calls: Vec<&Remark> = if annotation.scope.function.is_some() {
+                remarks
+                    .iter()
+                    .filter(|remark| remark.pass == "inline" && annotation.is_mentioned_in(remark))
+                    .collect()
+            } else {
+                located().filter(|remark| remark.pass == "inline").collect()
+            };
+            return check_optimized(
+                annotation,
+                calls,
+                "a call was not inlined",
+                "no inlined call was found",
+            );
+        }
+        Directive::ExpectNo(kinds) => (
+            located_missed()
+                .filter(|remark| kinds.iter().any(|kind| matches_kind(remark, kind)))
+                .collect(),
+            format!("unexpected remark(s) of kind {}", kinds.join(", ")),
+        ),
+        Directive::Expect(kind) => {
+            if located_missed().any(|remark| matches_kind(remark, kind)) {
+                return None;
+            }
+            return Some(BrokenExpectation {
+                annotation,
+                remarks: vec![],
+                reason: format!("no remark of kind {kind} was found"),
+            });
+        }
+        Directive::Allow(_) => return None,
+    };
+
+    if remarks.is_empty() {
+        None
+    } else {
+        Some(BrokenExpectation {
+            annotation,
+            remarks,
+            reason,
+        })
+    }
+}
+
+/// Checks that an optimization was applied at least once in the scope of the annotation and
+/// that it was never missed. Without a passed remark, the annotation could be misplaced or the
+/// code might not have been optimized at all.
+fn check_optimized<'a, I: IntoIterator<Item = &'a Remark>>(
+    annotation: &'a Annotation,
+    remarks: I,
+    missed_reason: &str,
+    not_found_reason: &str,
+) -> Option<BrokenExpectation<'a>> {
+    let (missed, passed): (Vec<&Remark>, Vec<&Remark>) = remarks
+        .into_iter()
+        .filter(|remark| remark.remark_type != RemarkType::Analysis)
+        .partition(|remark| remark.remark_type == RemarkType::Missed);
+    let reason = if !missed.is_empty() {
+        missed_reason
+    } else if passed.is_empty() {
+        not_found_reason
+    } else {
+        return None;
+    };
+    Some(BrokenExpectation {
+        annotation,
+        remarks: missed,
+        reason: reason.to_string(),
+    })
+}
+
+/// Writes a human-readable report of broken expectations.
+pub fn write_expectation_report<W: Write>(
+    broken: &[BrokenExpectation],
+    checked: usize,
+    mut writer: W,
+) -> std::io::Result<()> {
+    for expectation in broken {
+        let annotation = expectation.annotation;
+        let scope = match &annotation.scope.function {
+            Some(function) => format!("function `{function}`"),
+            None if annotation.scope.start == annotation.scope.end => {
+                format!("line {}", annotation.scope.start)
+            }
+            None => format!("lines {}-{}", annotation.scope.start, annotation.scope.end),
+        };
+        writeln!(
+            writer,
+            "{} {}:{} `{}` ({scope}): {}",
+            "BROKEN".red().bold(),
+            annotation.file,
+            annotation.line,
+            annotation.text,
+            expectation.reason
+        )?;
+        for remark in &expectation.remarks {
+            write_remark(&mut writer, "    -".red(), remark)?;
+        }
+    }
+    writeln!(
+        writer,
+        "{checked} expectation(s) checked, {} broken",
+        broken.len().to_string().red()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::annotation::expect::check_expectations;
+    use crate::annotation::parse_annotations;
+    use crate::remark::fixtures::{self, remark};
+    use crate::remark::{MessagePart, RemarkType};
+
+    const SOURCE: &str = r#"
+// remark: expect-inlined
+fn helper() {}
+
+fn sum(data: &[u32]) -> u32 {
+    let mut sum = 0;
+    // remark: expect-vectorized
+    for item in data {
+        sum += item;
+    }
+    helper(); // remark: expect-no NoDefinition
+    sum
+}
+"#;
+
+    #[test]
+    fn expectations_fulfilled() {
+        let annotations = parse_annotations("src/lib.rs", SOURCE);
+        let remark = |pass: &str, name: &str, line: u32| {
+            remark(pass, name)
+                .in_function("foo::sum")
+                .at("src/lib.rs", line)
+        };
+        let remarks = vec![
+            remark("loop-vectorize", "Vectorized", 8).with_type(RemarkType::Passed),
+            remark("inline", "Inlined", 11)
+                .with_message(helper_message())
+                .with_type(RemarkType::Passed),
+            remark("inline", "NoDefinition", 8),
+        ];
+        assert!(check_expectations(&annotations, &remarks).is_empty());
+    }
+
+    #[test]
+    fn expectations_without_remarks() {
+        let annotations = parse_annotations("src/lib.rs", SOURCE);
+        let broken = check_expectations(&annotations, &[]);
+        let broken: Vec<(&str, &str)> = broken
+            .iter()
+            .map(|broken| (broken.annotation.text.as_str(), broken.reason.as_str()))
+            .collect();
+        assert_eq!(
+            broken,
+            vec![
+                ("expect-inlined", "no inlined call was found"),
+                ("expect-vectorized", "no vectorized loop was found"),
+            ]
+        );
+    }
+
+    #[test]
+    fn expectations_broken() {
+        let annotations = parse_annotations("src/lib.rs", SOURCE);
+        let remark = |pass: &str, name: &str, line: u32| {
+            remark(pass, name)
+                .in_function("foo::sum")
+                .at("src/lib.rs", line)
+        };
+        let remarks = vec![
+            remark("loop-vectorize", "MissedDetails", 8),
+            remark("inline", "TooCostly", 11).with_message(helper_message()),
+            remark("inline", "NoDefinition", 11),
+        ];
+        let broken = check_expectations(&annotations, &remarks);
+        let broken: Vec<(&str, usize)> = broken
+            .iter()
+            .map(|broken| (broken.annotation.text.as_str(), broken.remarks.len()))
+            .collect();
+        assert_eq!(
+            broken,
+            vec![
+                ("expect-inlined", 1),
+                ("expect-vectorized", 1),
+                ("expect-no NoDefinition", 1)
+            ]
+        );
+    }
+
+    /// Message of a remark about a call to `helper`.
+    fn helper_message() -> Vec<MessagePart> {
+        vec![
+            fixtures::annotated("foo::helper", fixtures::location("src/lib.rs", 3, 0)),
+            fixtures::text(" message"),
+        ]
+    }
+}
@@ -0,0 +1,509 @@
+//! Annotations written in source code comments, e.g. `// remark: expect-vectorized`.
+//!
+//! An annotation applies to the line on which it is written, if it follows some code, or to
+//! the next line with code otherwise. If that line opens a block (e.g. a function or a loop),
+//! the annotation applies to the whole block.
+//!
+//! Only comments that start with `remark:` and contain a known directive are annotations. Other
+//! comments that start with `remark:` are ignored with a warning.
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use cargo_metadata::Metadata;
+use regex::Regex;
+
+use crate::remark::{Line, Location, MessagePart, Remark};
+
+pub mod expect;
+pub mod suppress;
+
+/// Prefix of comments that contain annotations (after `//`).
+const ANNOTATION_PREFIX: &str = "remark:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Directive {
+    /// `expect-vectorized`: loops in the scope must be vectorized, and there must be at least one
+    /// vectorized loop.
+    ExpectVectorized,
+    /// `expect-inlined`: calls in the scope must be inlined, and there must be at least one
+    /// inlined call. If the scope is a function, calls to the function are checked.
+    ExpectInlined,
+    /// `expect-no <kind>[, <kind>...]`: no missed remark of the given kinds (or passes) in the
+    /// scope.
+    ExpectNo(Vec<String>),
+    /// `expect <kind>`: at least one missed remark of the given kind (or pass) in the scope.
+    Expect(String),
+    /// `allow(<kind>[, <kind>...])`: remarks of the given kinds (or passes) in the scope are
+    /// suppressed. If the scope is a function, remarks about calls to the function are
+    /// suppressed too.
+    Allow(Vec<String>),
+}
+
+impl Directive {
+    /// Returns true if the directive is an expectation checked by `cargo remark check`.
+    pub fn is_expectation(&self) -> bool {
+        !matches!(self, Directive::Allow(_))
+    }
+}
+
+/// Source lines to which an annotation applies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    pub start: Line,
+    pub end: Line,
+    /// Name of the function, if the scope is a function definition.
+    pub function: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    /// Path of the source file, relative to the source directory.
+    pub file: String,
+    /// Line with the annotation comment.
+    pub line: Line,
+    pub scope: Scope,
+    pub directive: Directive,
+    /// Text of the directive, as written in the source.
+    pub text: String,
+}
+
+impl Annotation {
+    pub fn contains(&self, location: &Location) -> bool {
+        location.file == self.file
+            && location.line >= self.scope.start
+            && location.line <= self.scope.end
+    }
+
+    /// Returns true if the remark is located in the scope of the annotation.
+    pub fn is_located_in(&self, remark: &Remark) -> bool {
+        remark
+            .function
+            .location
+            .as_ref()
+            .map(|location| self.contains(location))
+            .unwrap_or(false)
+    }
+
+    /// Returns true if the remark mentions a function defined in the scope of the annotation
+    /// (e.g. as a callee which was not inlined).
+    pub fn is_mentioned_in(&self, remark: &Remark) -> bool {
+        remark.message.iter().any(|part| match part {
+            MessagePart::AnnotatedString { location, .. } => self.contains(location),
+            MessagePart::String(_) => false,
+        })
+    }
+}
+
+/// Parses annotations from the source code of a single file.
+/// Comments that start with `remark:`, but do not contain a valid directive, are skipped with a
+/// warning.
+pub fn parse_annotations(file: &str, source: &str) -> Vec<Annotation> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut annotations = vec![];
+
+    for (index, line) in lines.iter().enumerate() {
+        let Some(position) = find_comment(line) else {
+            continue;
+        };
+        let Some(text) = line[position + 2..]
+            .trim_start()
+            .strip_prefix(ANNOTATION_PREFIX)
+        else {
+            continue;
+        };
+        let text = text.trim();
+        let directive = match parse_directive(text) {
+            Ok(directive) => directive,
+            Err(error) => {
+                log::warn!(
+                    "Ignoring comment at {file}:{}, it is not a valid annotation: {error}",
+                    index + 1
+                );
+                continue;
+            }
+        };
+
+        let target = if line[..position].trim().is_empty() {
+            match find_next_code_line(&lines, index + 1) {
+                Some(target) => target,
+                None => {
+                    log::warn!(
+                        "Ignoring annotation at {file}:{}, it does not precede any code",
+                        index + 1
+                    );
+                    continue;
+                }
+            }
+        } else {
+            index
+        };
+
+        annotations.push(Annotation {
+            file: file.to_string(),
+            line: index as Line + 1,
+            scope: find_scope(&lines, target),
+            directive,
+            text: text.to_string(),
+        });
+    }
+    annotations
+}
+
+/// Finds annotations in all Rust source files in the given `roots`, which can be directories
+/// (searched recursively) or files. Paths of the annotations are relative to `source_dir`.
+pub fn find_annotations(source_dir: &Path, roots: &[PathBuf]) -> anyhow::Result<Vec<Annotation>> {
+    let mut files = vec![];
+    for root in roots {
+        if root.is_dir() {
+            collect_source_files(root, &mut files)?;
+        } else if root.is_file() {
+            files.push(root.clone());
+        }
+    }
+    files.sort();
+    files.dedup();
+
+    let mut annotations = vec![];
+    for path in files {
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read source file {}", path.display()))?;
+        if !source.contains(ANNOTATION_PREFIX) {
+            continue;
+        }
+        let file = path
+            .strip_prefix(source_dir)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        annotations.extend(parse_annotations(&file, &source));
+    }
+    Ok(annotations)
+}
+
+/// Finds the source roots of workspace packages, i.e. the directories of their targets (e.g.
+/// `src`, `tests` or `examples`). Targets located directly in a package directory (e.g. build
+/// scripts) are returned as files, so that the whole package directory (which can contain the
+/// target directory) is not searched.
+pub fn find_source_roots(metadata: &Metadata) -> Vec<PathBuf> {
+    let mut roots = vec![];
+    for package in metadata.workspace_packages() {
+        let package_dir = package.manifest_path.parent();
+        for target in &package.targets {
+            let src_path = target.src_path.as_std_path();
+            match target.src_path.parent() {
+                Some(dir) if Some(dir) != package_dir => {
+                    roots.push(dir.as_std_path().to_path_buf())
+                }
+                _ => roots.push(src_path.to_path_buf()),
+            }
+        }
+    }
+    roots.sort();
+    roots.dedup();
+    // Nested directories are searched as a part of their parent
+    let mut result: Vec<PathBuf> = vec![];
+    for root in roots {
+        if !result
+            .iter()
+            .any(|parent| root.starts_with(parent) && parent.is_dir())
+        {
+            result.push(root);
+        }
+    }
+    result
+}
+
+fn collect_source_files(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Cannot read directory {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            // Cargo marks target directories with a `CACHEDIR.TAG` file
+            let cache = path.join("CACHEDIR.TAG").is_file();
+            if !hidden && !cache {
+                collect_source_files(&path, files)?;
+            }
+        } else if file_type.is_file() && path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Finds the start of a `//` comment on the line, ignoring `//` in string literals.
+/// Doc comments (`///` and `//!`) are ignored.
+fn find_comment(line: &str) -> Option<usize> {
+    let strings: Vec<Range<usize>> = string_regex()
+        .find_iter(line)
+        .map(|string| string.range())
+        .collect();
+    let position = line
+        .match_indices("//")
+        .map(|(position, _)| position)
+        .find(|position| !strings.iter().any(|string| string.contains(position)))?;
+    match line[position + 2..].chars().next() {
+        Some('/' | '!') => None,
+        _ => Some(position),
+    }
+}
+
+/// Checks that the arguments of a directive are remark kinds or pass names.
+fn check_kinds(kinds: &[String]) -> anyhow::Result<()> {
+    match kinds.iter().find(|kind| {
+        !kind
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }) {
+        Some(kind) => Err(anyhow::anyhow!(
+            "`{kind}` is not a remark kind or a pass name"
+        )),
+        None => Ok(()),
+    }
+}
+
+fn parse_directive(text: &str) -> anyhow::Result<Directive> {
+    if let Some(args) = text.strip_prefix("allow") {
+        let kinds: Vec<String> = args
+            .trim()
+            .strip_prefix('(')
+            .and_then(|args| args.strip_suffix(')'))
+            .map(|args| {
+                args.split(',')
+                    .map(|arg| arg.trim())
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| arg.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if kinds.is_empty() {
+            return Err(anyhow::anyhow!(
+                "`allow` needs a list of remark kinds, e.g. `allow(NoDefinition)`"
+            ));
+        }
+        check_kinds(&kinds)?;
+        return Ok(Directive::Allow(kinds));
+    }
+    let (name, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let args: Vec<String> = args
+        .split(',')
+        .map(|arg| arg.trim())
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| arg.to_string())
+        .collect();
+    check_kinds(&args)?;
+    match name {
+        "expect-vectorized" if args.is_empty() => Ok(Directive::ExpectVectorized),
+        "expect-inlined" if args.is_empty() => Ok(Directive::ExpectInlined),
+        "expect-no" if !args.is_empty() => Ok(Directive::ExpectNo(args)),
+        "expect" if args.len() == 1 => Ok(Directive::Expect(args[0].clone())),
+        "expect-vectorized" | "expect-inlined" => {
+            Err(anyhow::anyhow!("`{name}` does not take any arguments"))
+        }
+        "expect-no" => Err(anyhow::anyhow!(
+            "`expect-no` needs a list of remark kinds, e.g. `expect-no NeverInline`"
+        )),
+        "expect" => Err(anyhow::anyhow!(
+            "`expect` needs a single remark kind, e.g. `expect NoDefinition`"
+        )),
+        _ => Err(anyhow::anyhow!("Unknown directive `{text}`")),
+    }
+}
+
+/// Finds the next line that contains code, skipping empty lines, comments and attributes.
+fn find_next_code_line(lines: &[&str], start: usize) -> Option<usize> {
+    (start..lines.len()).find(|&index| {
+        let line = lines[index].trim();
+        !(line.is_empty() || line.starts_with("//") || line.starts_with("#["))
+    })
+}
+
+fn find_scope(lines: &[&str], target: usize) -> Scope {
+    let function = FUNCTION_REGEX
+        .get_or_init(|| {
+            Regex::new(
+                r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe|extern(?:\s+"[^"]*")?)\s+)*fn\s+(\w+)"#,
+            )
+            .expect("Could not create regular expression")
+        })
+        .captures(lines[target])
+        .map(|captures| captures[1].to_string());
+
+    // Find the end of the block opened on the target line, or of the statement on it
+    let mut depth = 0;
+    let mut end = target;
+    'lines: for (index, line) in lines.iter().enumerate().skip(target) {
+        end = index;
+        for c in strip_strings_and_comments(line).chars() {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => {
+                    depth -= 1;
+                    if depth < 0 {
+                        // The enclosing block has ended (e.g. after a tail expression)
+                        end = index.saturating_sub(1).max(target);
+                        break 'lines;
+                    }
+                    if depth == 0 && c == '}' {
+                        break 'lines;
+                    }
+                }
+                ';' | ',' if depth == 0 => break 'lines,
+                _ => {}
+            }
+        }
+    }
+
+    Scope {
+        start: target as Line + 1,
+        end: end as Line + 1,
+        function,
+    }
+}
+
+static FUNCTION_REGEX: OnceLock<Regex> = OnceLock::new();
+static STRING_REGEX: OnceLock<Regex> = OnceLock::new();
+
+fn string_regex() -> &'static Regex {
+    STRING_REGEX.get_or_init(|| {
+        Regex::new(r#""(?:\\.|[^"\\])*"|'(?:\\.|[^'\\])'"#)
+            .expect("Could not create regular expression")
+    })
+}
+
+fn strip_strings_and_comments(line: &str) -> String {
+    let line = string_regex().replace_all(line, "");
+    match line.find("//") {
+        Some(position) => line[..position].to_string(),
+        None => line.into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::annotation::{parse_annotations, Directive, Scope};
+
+    #[test]
+    fn parse_function_scope() {
+        let source = r#"
+// remark: expect-inlined
+#[inline]
+pub fn helper(a: u32) -> u32 {
+    a + 1
+}
+"#;
+        let annotations = parse_annotations("src/lib.rs", source);
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].directive, Directive::ExpectInlined);
+        assert_eq!(
+            annotations[0].scope,
+            Scope {
+                start: 4,
+                end: 6,
+                function: Some("helper".to_string())
+            }
+        );
+    }
+
+    #[test]
+    fn parse_loop_scope() {
+        let source = r#"
+fn sum(data: &[u32]) -> u32 {
+    let mut sum = 0;
+    // remark: expect-vectorized
+    for item in data {
+        sum += item; // "}"
+    }
+    sum
+}
+"#;
+        let annotations = parse_annotations("src/lib.rs", source);
+        assert_eq!(
+            annotations[0].scope,
+            Scope {
+                start: 5,
+                end: 7,
+                function: None
+            }
+        );
+    }
+
+    #[test]
+    fn parse_trailing_comment() {
+        let source = r#"
+fn main() {
+    foo(); // remark: expect-no NeverInline, NoDefinition
+    bar();
+}
+"#;
+        let annotations = parse_annotations("src/main.rs", source);
+        assert_eq!(
+            annotations[0].directive,
+            Directive::ExpectNo(vec!["NeverInline".to_string(), "NoDefinition".to_string()])
+        );
+        assert_eq!(
+            annotations[0].scope,
+            Scope {
+                start: 3,
+                end: 3,
+                function: None
+            }
+        );
+    }
+
+    #[test]
+    fn parse_multiline_statement() {
+        let source = r#"
+fn main() {
+    // remark: expect NoDefinition
+    let x = foo(
+        1,
+    );
+}
+"#;
+        let annotations = parse_annotations("src/main.rs", source);
+        assert_eq!(
+            annotations[0].directive,
+            Directive::Expect("NoDefinition".to_string())
+        );
+        assert_eq!(
+            annotations[0].scope,
+            Scope {
+                start: 4,
+                end: 6,
+                function: None
+            }
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let annotations = parse_annotations("src/main.rs", "// remark: expect-fast\nfn foo() {}");
+        assert!(annotations.is_empty());
+    }
+
+    #[test]
+    fn ignore_prose_comments() {
+        let source = r#"
+// remark: this is slow
+// remark: expect this to be fast
+fn foo() {
+    let a = "// remark: allow"; // TODO remark: allow
+    /// remark: allow
+    bar(); // remark: allow(Foo)
+}
+"#;
+        let annotations = parse_annotations("src/main.rs", source);
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].line, 7);
+        assert_eq!(
+            annotations[0].directive,
+            Directive::Allow(vec!["Foo".to_string()])
+        );
+    }
+}
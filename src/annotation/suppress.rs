@@ -0,0 +1,235 @@
+//! Suppression of known remarks, either by `// remark: allow(...)` annotations or by an
+//! acknowledgement file.
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::annotation::{find_annotations, Annotation, Directive};
+use crate::remark::Remark;
+
+/// Name of the acknowledgement file that is loaded from the workspace root by default.
+pub const DEFAULT_ACK_FILE: &str = "remark-ack.toml";
+
+/// Acknowledgement of a known remark.
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AckEntry {
+    /// Demangled name of the function containing the remark.
+    pub function: String,
+    /// Remark kind (name).
+    pub kind: String,
+    /// Why is the remark acceptable.
+    pub reason: String,
+}
+
+/// File with acknowledged remarks, in the following format:
+/// ```toml
+/// [[remark]]
+/// function = "mycrate::cold_path"
+/// kind = "NeverInline"
+/// reason = "Error handling is not performance critical"
+/// ```
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AckFile {
+    #[serde(default, rename = "remark")]
+    pub remarks: Vec<AckEntry>,
+}
+
+impl AckFile {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read acknowledgement file {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Cannot parse acknowledgement file {}", path.display()))
+    }
+}
+
+pub struct SuppressedRemark {
+    pub remark: Remark,
+    /// Why was the remark suppressed.
+    pub reason: String,
+}
+
+/// Decides which remarks should be suppressed.
+#[derive(Default)]
+pub struct Suppressions {
+    annotations: Vec<Annotation>,
+    acks: Vec<AckEntry>,
+}
+
+impl Suppressions {
+    pub fn new(annotations: Vec<Annotation>, ack_file: AckFile) -> Self {
+        Self {
+            annotations: annotations
+                .into_iter()
+                .filter(|annotation| matches!(annotation.directive, Directive::Allow(_)))
+                .collect(),
+            acks: ack_file.remarks,
+        }
+    }
+
+    /// Loads `allow` annotations from the sources in `source_roots` (see [`find_annotations`])
+    /// and acknowledged remarks from `ack_file`. If no file is given, [`DEFAULT_ACK_FILE`] is
+    /// loaded from `source_dir`, if it exists.
+    pub fn load(
+        source_dir: &Path,
+        source_roots: &[PathBuf],
+        ack_file: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        let annotations = find_annotations(source_dir, source_roots)?;
+        let ack_file = match ack_file {
+            Some(path) => AckFile::load(path)?,
+            None => {
+                let path = source_dir.join(DEFAULT_ACK_FILE);
+                if path.is_file() {
+                    AckFile::load(&path)?
+                } else {
+                    AckFile::default()
+                }
+            }
+        };
+        Ok(Self::new(annotations, ack_file))
+    }
+
+    /// Same as [`Suppressions::load`], but a failure is only reported as a warning and no
+    /// remarks are suppressed then.
+    pub fn load_or_warn(
+        source_dir: &Path,
+        source_roots: &[PathBuf],
+        ack_file: Option<&Path>,
+    ) -> Self {
+        Self::load(source_dir, source_roots, ack_file).unwrap_or_else(|error| {
+            log::warn!("Cannot load suppressions, no remarks will be suppressed: {error:?}");
+            Self::default()
+        })
+    }
+
+    /// Returns the reason why the remark should be suppressed, or `None` if it should be kept.
+    ///
+    /// Remarks without a location can only be suppressed by an annotation placed on a function
+    /// that they mention (e.g. as a callee), or by the acknowledgement file.
+    pub fn find_reason(&self, remark: &Remark) -> Option<String> {
+        for annotation in &self.annotations {
+            let Directive::Allow(kinds) = &annotation.directive else {
+                continue;
+            };
+            if !kinds
+                .iter()
+                .any(|kind| kind == &remark.name || kind == &remark.pass)
+            {
+                continue;
+            }
+            // Allowing a remark on a function also allows remarks about calls to the function
+            if annotation.is_located_in(remark)
+                || (annotation.scope.function.is_some() && annotation.is_mentioned_in(remark))
+            {
+                return Some(format!(
+                    "allowed by `{}` at {}:{}",
+                    annotation.text, annotation.file, annotation.line
+                ));
+            }
+        }
+        self.acks
+            .iter()
+            .find(|ack| ack.function == remark.function.name && ack.kind == remark.name)
+            .map(|ack| ack.reason.clone())
+    }
+
+    /// Splits remarks into remarks that should be kept and remarks that should be suppressed.
+    pub fn partition(&self, remarks: Vec<Remark>) -> (Vec<Remark>, Vec<SuppressedRemark>) {
+        let mut kept = vec![];
+        let mut suppressed = vec![];
+        for remark in remarks {
+            match self.find_reason(&remark) {
+                Some(reason) => suppressed.push(SuppressedRemark { remark, reason }),
+                None => kept.push(remark),
+            }
+        }
+        (kept, suppressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::annotation::parse_annotations;
+    use crate::annotation::suppress::{AckFile, Suppressions};
+    use crate::remark::fixtures::{self, remark};
+    use crate::remark::MessagePart;
+
+    const SOURCE: &str = r#"
+// remark: allow(NeverInline)
+#[inline(never)]
+fn cold() {}
+
+fn main() {
+    cold();
+    foo(); // remark: allow(NoDefinition)
+}
+"#;
+
+    #[test]
+    fn suppress_by_annotation() {
+        let annotations = parse_annotations("src/main.rs", SOURCE);
+        let suppressions = Suppressions::new(annotations, AckFile::default());
+
+        let remarks = vec![
+            remark("inline", "NeverInline")
+                .at("src/main.rs", 7)
+                .with_message(cold_message()),
+            remark("inline", "NoDefinition").at("src/main.rs", 8),
+            remark("inline", "NoDefinition").at("src/main.rs", 7),
+        ];
+        let (kept, suppressed) = suppressions.partition(remarks);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].function.location.as_ref().unwrap().line, 7);
+        insta::assert_snapshot!(suppressed.iter().map(|s| s.reason.as_str()).collect::<Vec<_>>().join("\n"), @r###"
+        allowed by `allow(NeverInline)` at src/main.rs:2
+        allowed by `allow(NoDefinition)` at src/main.rs:8
+        "###);
+    }
+
+    #[test]
+    fn suppress_unlocated() {
+        let annotations = parse_annotations("src/main.rs", SOURCE);
+        let suppressions = Suppressions::new(annotations, AckFile::default());
+
+        let mentioned = remark("inline", "NeverInline")
+            .unlocated()
+            .with_message(cold_message());
+        let unmentioned = remark("inline", "NoDefinition").unlocated();
+        assert!(suppressions.find_reason(&mentioned).is_some());
+        assert!(suppressions.find_reason(&unmentioned).is_none());
+    }
+
+    #[test]
+    fn suppress_by_ack_file() {
+        let ack_file: AckFile = toml::from_str(
+            r#"
+[[remark]]
+function = "foo::cold"
+kind = "NoDefinition"
+reason = "Cold path"
+"#,
+        )
+        .unwrap();
+        let suppressions = Suppressions::new(vec![], ack_file);
+        assert_eq!(
+            suppressions
+                .find_reason(&remark("inline", "NoDefinition").in_function("foo::cold"))
+                .as_deref(),
+            Some("Cold path")
+        );
+        assert!(suppressions
+            .find_reason(&remark("inline", "TooCostly").in_function("foo::cold"))
+            .is_none());
+    }
+
+    /// Message of a remark about a call to `cold`.
+    fn cold_message() -> Vec<MessagePart> {
+        vec![
+            fixtures::annotated("foo::cold", fixtures::location("src/main.rs", 4, 0)),
+            fixtures::text(" message"),
+        ]
+    }
+}
@@ -0,0 +1,281 @@
+//! Baseline of known remarks, which can be checked into a repository and used to detect
+//! newly introduced remarks on CI.
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Context;
+use colored::Colorize;
+use regex::Regex;
+
+use crate::remark::write_remark;
+use crate::remark::Remark;
+use crate::utils::data_structures::Map;
+
+/// Version of the baseline file format.
+pub const BASELINE_VERSION: u32 = 1;
+
+/// Group of remarks with the same pass, name, function and file.
+/// Line numbers are intentionally not stored, so that the baseline does not have to be updated
+/// whenever the code around a remark shifts.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub struct BaselineEntry {
+    pub pass: String,
+    pub name: String,
+    pub function: String,
+    pub file: Option<String>,
+    /// How many remarks with this key were generated.
+    pub count: usize,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub remarks: Vec<BaselineEntry>,
+}
+
+type BaselineKey = (String, String, String, Option<String>);
+
+fn baseline_key(remark: &Remark) -> BaselineKey {
+    (
+        remark.pass.clone(),
+        remark.name.clone(),
+        remark.function.name.clone(),
+        remark.function.location.as_ref().map(|l| l.file.clone()),
+    )
+}
+
+impl Baseline {
+    /// Creates a baseline from the given remarks.
+    /// The entries are sorted, so that the output is stable between runs.
+    pub fn from_remarks(remarks: &[Remark]) -> Self {
+        let mut counts: Map<BaselineKey, usize> = Map::default();
+        for remark in remarks {
+            *counts.entry(baseline_key(remark)).or_default() += 1;
+        }
+        let mut entries: Vec<BaselineEntry> = counts
+            .into_iter()
+            .map(|((pass, name, function, file), count)| BaselineEntry {
+                pass,
+                name,
+                function,
+                file,
+                count,
+            })
+            .collect();
+        entries.sort();
+        Self {
+            version: BASELINE_VERSION,
+            remarks: entries,
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Cannot open baseline file {}", path.display()))?;
+        let baseline: Baseline = serde_json::from_reader(std::io::BufReader::new(file))
+            .with_context(|| format!("Cannot parse baseline file {}", path.display()))?;
+        if baseline.version != BASELINE_VERSION {
+            return Err(anyhow::anyhow!(
+                "Baseline file {} has version {}, but version {BASELINE_VERSION} is expected. Regenerate it with `cargo remark check --save-baseline`.",
+                path.display(),
+                baseline.version
+            ));
+        }
+        Ok(baseline)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut content = serde_json::to_string_pretty(self)?;
+        content.push('\n');
+        std::fs::write(path, content)
+            .with_context(|| format!("Cannot write baseline file {}", path.display()))
+    }
+
+    /// Finds remarks that are not covered by the baseline.
+    ///
+    /// If there are more remarks with the same key than recorded in the baseline, the
+    /// remarks with the highest line numbers are reported as new.
+    pub fn find_new_remarks<'a>(&self, remarks: &'a [Remark]) -> Vec<&'a Remark> {
+        let known: Map<BaselineKey, usize> = self
+            .remarks
+            .iter()
+            .map(|entry| {
+                (
+                    (
+                        entry.pass.clone(),
+                        entry.name.clone(),
+                        entry.function.clone(),
+                        entry.file.clone(),
+                    ),
+                    entry.count,
+                )
+            })
+            .collect();
+
+        let mut groups: Map<BaselineKey, Vec<&Remark>> = Map::default();
+        for remark in remarks {
+            groups.entry(baseline_key(remark)).or_default().push(remark);
+        }
+
+        let mut new_remarks = vec![];
+        for (key, mut group) in groups {
+            let known = known.get(&key).copied().unwrap_or(0);
+            if group.len() > known {
+                group.sort_by_key(|remark| {
+                    remark
+                        .function
+                        .location
+                        .as_ref()
+                        .map(|l| (l.line, l.column))
+                });
+                new_remarks.extend(group.into_iter().skip(known));
+            }
+        }
+        new_remarks.sort_by_key(|remark| {
+            remark
+                .function
+                .location
+                .as_ref()
+                .map(|l| (l.file.clone(), l.line, l.column))
+        });
+        new_remarks
+    }
+}
+
+/// Decides which new remarks should fail the check.
+/// If no kinds nor functions are configured, all new remarks are denied.
+#[derive(Default)]
+pub struct DenyFilter {
+    /// Remark kinds (names), e.g. `NoDefinition`.
+    pub kinds: Vec<String>,
+    /// Regular expressions matched against (demangled) function names.
+    pub functions: Vec<Regex>,
+}
+
+impl DenyFilter {
+    pub fn is_denied(&self, remark: &Remark) -> bool {
+        if self.kinds.is_empty() && self.functions.is_empty() {
+            return true;
+        }
+        self.kinds.iter().any(|kind| kind == &remark.name)
+            || self
+                .functions
+                .iter()
+                .any(|function| function.is_match(&remark.function.name))
+    }
+}
+
+/// Writes a human-readable list of new remarks that have failed the check.
+pub fn write_new_remarks<W: Write>(remarks: &[&Remark], mut writer: W) -> std::io::Result<()> {
+    for remark in remarks {
+        write_remark(&mut writer, "+".red(), remark)?;
+    }
+    writeln!(
+        writer,
+        "{} new remark(s) not present in the baseline",
+        remarks.len().to_string().red()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use crate::baseline::{Baseline, DenyFilter};
+    use crate::remark::fixtures::remark;
+
+    #[test]
+    fn baseline_counts() {
+        let baseline = Baseline::from_remarks(&[
+            remark("inline", "NoDefinition")
+                .in_function("foo::main")
+                .at("src/main.rs", 20),
+            remark("inline", "NoDefinition")
+                .in_function("foo::main")
+                .at("src/main.rs", 10),
+            remark("inline", "TooCostly")
+                .in_function("foo::bar")
+                .at("src/main.rs", 5),
+        ]);
+        insta::assert_snapshot!(serde_json::to_string_pretty(&baseline).unwrap(), @r###"
+        {
+          "version": 1,
+          "remarks": [
+            {
+              "pass": "inline",
+              "name": "NoDefinition",
+              "function": "foo::main",
+              "file": "src/main.rs",
+              "count": 2
+            },
+            {
+              "pass": "inline",
+              "name": "TooCostly",
+              "function": "foo::bar",
+              "file": "src/main.rs",
+              "count": 1
+            }
+          ]
+        }
+        "###);
+    }
+
+    #[test]
+    fn baseline_line_shift() {
+        let baseline = Baseline::from_remarks(&[
+            remark("inline", "NoDefinition")
+                .in_function("foo::main")
+                .at("src/main.rs", 10),
+            remark("inline", "TooCostly")
+                .in_function("foo::bar")
+                .at("src/main.rs", 20),
+        ]);
+        let remarks = vec![
+            remark("inline", "NoDefinition")
+                .in_function("foo::main")
+                .at("src/main.rs", 15),
+            remark("inline", "TooCostly")
+                .in_function("foo::bar")
+                .at("src/main.rs", 25),
+        ];
+        assert!(baseline.find_new_remarks(&remarks).is_empty());
+    }
+
+    #[test]
+    fn baseline_new_remarks() {
+        let baseline = Baseline::from_remarks(&[remark("inline", "NoDefinition")
+            .in_function("foo::main")
+            .at("src/main.rs", 10)]);
+        let remarks = vec![
+            remark("inline", "NoDefinition")
+                .in_function("foo::main")
+                .at("src/main.rs", 10),
+            remark("inline", "NoDefinition")
+                .in_function("foo::main")
+                .at("src/main.rs", 30),
+            remark("inline", "TooCostly")
+                .in_function("foo::bar")
+                .at("src/main.rs", 20),
+        ];
+        let new = baseline.find_new_remarks(&remarks);
+        assert_eq!(new.len(), 2);
+        assert_eq!(new[0].name, "TooCostly");
+        assert_eq!(new[1].function.location.as_ref().unwrap().line, 30);
+    }
+
+    #[test]
+    fn deny_filter() {
+        let all = DenyFilter::default();
+        assert!(all.is_denied(&remark("inline", "TooCostly").in_function("foo::bar")));
+
+        let filter = DenyFilter {
+            kinds: vec!["NoDefinition".to_string()],
+            functions: vec![Regex::new("^foo::hot").unwrap()],
+        };
+        assert!(filter.is_denied(&remark("inline", "NoDefinition").in_function("foo::bar")));
+        assert!(filter.is_denied(&remark("inline", "TooCostly").in_function("foo::hot_loop")));
+        assert!(!filter.is_denied(&remark("inline", "TooCostly").in_function("foo::bar")));
+    }
+}
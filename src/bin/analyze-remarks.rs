@@ -1,12 +1,19 @@
+use cargo_remark::annotation::find_source_roots;
+use cargo_remark::annotation::suppress::Suppressions;
+use cargo_remark::build::get_cargo_ctx;
+use cargo_remark::changes::filter_changed_since;
+use cargo_remark::export::{export_remarks, open_output, sort_remarks, OutputFormat, RemarkSort};
+use cargo_remark::knowledge::KnowledgeBase;
 use cargo_remark::remark::{load_remarks_from_dir, RemarkLoadOptions};
 use cargo_remark::render::render_remarks;
 use cargo_remark::utils::callback::ProgressBarCallback;
+use cargo_remark::utils::cli::configure_output_colors;
 use cargo_remark::utils::open_result;
 use cargo_remark::utils::timing::time_block_print;
 use cargo_remark::RustcSourceRoot;
 use clap::Parser;
 use env_logger::Env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[cfg(feature = "mimalloc")]
 #[global_allocator]
@@ -50,6 +57,47 @@ struct Args {
     /// Open the generated website after the build finishes.
     #[arg(long)]
     open: bool,
+
+    /// Output format of the remarks.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Html)]
+    format: OutputFormat,
+
+    /// File into which the remarks will be written, for formats other than `html`.
+    /// The remarks are written to stdout by default.
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Order of the remarks, for formats other than `html`.
+    #[arg(long, value_enum, default_value_t = RemarkSort::File)]
+    sort: RemarkSort,
+
+    /// File with acknowledged remarks, which will be suppressed.
+    /// `remark-ack.toml` in the source directory is used by default, if it exists.
+    #[arg(long)]
+    ack_file: Option<PathBuf>,
+
+    /// File with explanations of remark kinds, which extend and override the built-in ones.
+    /// `remark-knowledge.toml` in the source directory is used by default, if it exists.
+    #[arg(long)]
+    knowledge_file: Option<PathBuf>,
+
+    /// Show suppressed remarks greyed out in the remark list of the website.
+    #[arg(long)]
+    show_suppressed: bool,
+
+    /// Also load passed remarks (applied optimizations), e.g. vectorized loops.
+    #[arg(long)]
+    passed: bool,
+
+    /// Also load analysis remarks, which e.g. explain why a loop was not vectorized.
+    /// Always enabled for the `metrics-json` format.
+    #[arg(long)]
+    analysis: bool,
+
+    /// Only keep remarks located on lines that were changed since the given git revision
+    /// (e.g. `origin/main`), or that mention such lines. Uncommitted changes are included.
+    #[arg(long, value_name = "REV")]
+    changed_since: Option<String>,
 }
 
 fn analyze(args: Args) -> anyhow::Result<()> {
@@ -61,6 +109,15 @@ fn analyze(args: Args) -> anyhow::Result<()> {
         sysroot,
         filter_kind,
         open,
+        format,
+        output,
+        sort,
+        ack_file,
+        knowledge_file,
+        show_suppressed,
+        passed,
+        analysis,
+        changed_since,
     } = args;
 
     let rustc_source_root = sysroot
@@ -74,25 +131,77 @@ fn analyze(args: Args) -> anyhow::Result<()> {
                 source_dir: source_dir.clone(),
                 filter_kind,
                 rustc_source_root,
+                passed,
+                analysis: analysis || format == OutputFormat::MetricsJson,
+                unlocated: format == OutputFormat::Html,
+                spills: format == OutputFormat::Html,
             },
             Some(&ProgressBarCallback::default()),
         )
     })?;
-    time_block_print("Render", || {
-        render_remarks(
-            remarks,
-            &source_dir,
-            &output_dir,
-            Some(&ProgressBarCallback::default()),
-        )
-    })?;
-    open_result(&output_dir, open)?;
+    let suppressions = Suppressions::load_or_warn(
+        &source_dir,
+        &get_source_roots(&source_dir),
+        ack_file.as_deref(),
+    );
+    let (mut remarks, mut suppressed) = suppressions.partition(remarks);
+    if let Some(revision) = changed_since {
+        filter_changed_since(&source_dir, &revision, &mut remarks, Some(&mut suppressed))?;
+    }
+    if !suppressed.is_empty() {
+        log::info!("{} remark(s) suppressed", suppressed.len());
+    }
+    let knowledge = KnowledgeBase::load(&source_dir, knowledge_file.as_deref())?;
+
+    match format {
+        OutputFormat::Html => {
+            time_block_print("Render", || {
+                render_remarks(
+                    remarks,
+                    &suppressed,
+                    show_suppressed,
+                    &knowledge,
+                    &source_dir,
+                    &output_dir,
+                    Some(&ProgressBarCallback::default()),
+                )
+            })?;
+            open_result(&output_dir, open)?;
+        }
+        format => {
+            sort_remarks(&mut remarks, sort);
+            configure_output_colors(output.as_deref());
+            export_remarks(
+                &remarks,
+                format,
+                &source_dir,
+                &knowledge,
+                open_output(output.as_deref())?,
+            )?;
+        }
+    }
 
     Ok(())
 }
 
+/// Finds source roots that are searched for annotations. If the source directory contains a Cargo
+/// package, the directories of its targets are used, otherwise the whole directory is searched.
+fn get_source_roots(source_dir: &Path) -> Vec<PathBuf> {
+    let manifest_path = source_dir.join("Cargo.toml");
+    if manifest_path.is_file() {
+        match get_cargo_ctx(Some(&manifest_path), None) {
+            Ok(ctx) => return find_source_roots(&ctx.metadata),
+            Err(error) => log::warn!("{error:?}"),
+        }
+    }
+    vec![source_dir.to_path_buf()]
+}
+
 fn main() -> anyhow::Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("cargo_remark=info")).init();
+    env_logger::Builder::from_env(
+        Env::default().default_filter_or("cargo_remark=info,analyze_remarks=info"),
+    )
+    .init();
 
     let args = Args::parse();
     analyze(args)?;
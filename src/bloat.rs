@@ -0,0 +1,173 @@
+//! Report of monomorphization bloat, which adds up the code size of all instantiations of each
+//! generic function.
+//!
+//! Instantiations are distinguished by their mangled names, because legacy symbol names do not
+//! contain generic arguments, so all instantiations of a function have the same demangled name.
+//! Closures are not reported, because every closure of a function has the same generic origin.
+use crate::metrics::FunctionMetrics;
+use crate::utils::data_structures::Map;
+
+/// Generic function with multiple instantiations.
+#[derive(Debug)]
+pub struct GenericFunction<'a> {
+    /// Demangled name without generic arguments, e.g. `core::ptr::drop_in_place`.
+    pub name: String,
+    /// Instantiations sorted by their instruction count in descending order.
+    pub instantiations: Vec<&'a FunctionMetrics>,
+    /// Sum of the instruction counts of all instantiations.
+    pub total_instructions: u64,
+}
+
+/// Groups functions that have an instruction count by their generic origin (see
+/// [`generic_origin`]). Only functions with at least two instantiations are returned, sorted by
+/// their total instruction count in descending order. Closures are ignored.
+pub fn find_generic_bloat(metrics: &[FunctionMetrics]) -> Vec<GenericFunction<'_>> {
+    let mut functions: Map<String, Vec<&FunctionMetrics>> = Map::default();
+    for function in metrics {
+        if function.instruction_count.is_none() {
+            continue;
+        }
+        let origin = generic_origin(&function.function.name);
+        if !is_closure(&origin) {
+            functions.entry(origin).or_default().push(function);
+        }
+    }
+
+    let mut functions: Vec<GenericFunction> = functions
+        .into_iter()
+        .filter(|(_, instantiations)| instantiations.len() > 1)
+        .map(|(name, mut instantiations)| {
+            instantiations.sort_by(|a, b| {
+                b.instruction_count
+                    .cmp(&a.instruction_count)
+                    .then_with(|| a.function.name.cmp(&b.function.name))
+                    .then_with(|| a.function.mangled_name.cmp(&b.function.mangled_name))
+            });
+            let total_instructions = instantiations
+                .iter()
+                .filter_map(|function| function.instruction_count)
+                .sum();
+            GenericFunction {
+                name,
+                instantiations,
+                total_instructions,
+            }
+        })
+        .collect();
+    functions.sort_by(|a, b| {
+        b.total_instructions
+            .cmp(&a.total_instructions)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    functions
+}
+
+/// Returns true if the path (without generic arguments) belongs to a closure, e.g.
+/// `foo::bar::{{closure}}` (legacy mangling) or `foo::bar::{closure#0}` (v0 mangling).
+fn is_closure(path: &str) -> bool {
+    path.contains("{{closure}}") || path.contains("{closure#")
+}
+
+/// Removes generic arguments from a demangled function name, e.g.
+/// `<alloc::vec::Vec<u8> as core::ops::drop::Drop>::drop` becomes
+/// `<alloc::vec::Vec as core::ops::drop::Drop>::drop`.
+/// The angle brackets of qualified paths (`<Type as Trait>`) are kept.
+pub fn generic_origin(function: &str) -> String {
+    let mut origin = String::with_capacity(function.len());
+    // Nesting depth of the generic arguments that are being removed
+    let mut depth = 0;
+    let mut previous: Option<char> = None;
+    for c in function.chars() {
+        if depth > 0 {
+            match c {
+                '<' => depth += 1,
+                // Ignore arrows of function pointer types (`fn(u8) -> u8`)
+                '>' if previous != Some('-') => depth -= 1,
+                _ => {}
+            }
+        } else if c == '<'
+            && previous.is_some_and(|previous| {
+                previous.is_alphanumeric() || previous == '_' || previous == ':'
+            })
+        {
+            depth = 1;
+            // Turbofish of v0 symbol names (`foo::<u8>`)
+            if origin.ends_with("::") {
+                origin.truncate(origin.len() - 2);
+            }
+        } else {
+            origin.push(c);
+        }
+        previous = Some(c);
+    }
+    origin
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bloat::{find_generic_bloat, generic_origin};
+    use crate::metrics::FunctionMetrics;
+    use crate::remark::Function;
+
+    #[test]
+    fn remove_generic_arguments() {
+        let names = [
+            "foo::bar",
+            "foo::bar::<u32>",
+            "foo::bar::<alloc::vec::Vec<u8>>::{closure#0}",
+            "core::ptr::drop_in_place<std::rt::lang_start<()>::{{closure}}>",
+            "<alloc::vec::Vec<u8> as core::ops::drop::Drop>::drop",
+            "<foo::Foo as core::convert::From<fn(u8) -> u8>>::from",
+            "<[u8] as core::fmt::Debug>::fmt",
+        ];
+        insta::assert_snapshot!(names.iter().map(|name| generic_origin(name)).collect::<Vec<_>>().join("\n"), @r###"
+        foo::bar
+        foo::bar
+        foo::bar::{closure#0}
+        core::ptr::drop_in_place
+        <alloc::vec::Vec as core::ops::drop::Drop>::drop
+        <foo::Foo as core::convert::From>::from
+        <[u8] as core::fmt::Debug>::fmt
+        "###);
+    }
+
+    #[test]
+    fn group_instantiations() {
+        let metrics = vec![
+            metrics("foo::parse::<u8>", "_R1", Some(40)),
+            metrics("foo::parse::<u16>", "_R2", Some(60)),
+            metrics("foo::parse::<u32>", "_R3", None),
+            // Legacy symbol names have the same demangled name for all instantiations
+            metrics("core::ptr::drop_in_place", "_ZN1", Some(10)),
+            metrics("core::ptr::drop_in_place", "_ZN2", Some(10)),
+            metrics("core::ptr::drop_in_place", "_ZN3", Some(15)),
+            metrics("foo::main", "_ZN4", Some(500)),
+            // Closures of a function are not its instantiations
+            metrics("foo::run::{{closure}}", "_ZN5", Some(20)),
+            metrics("foo::run::{{closure}}", "_ZN6", Some(20)),
+            metrics("foo::run::<u8>::{closure#0}", "_R4", Some(20)),
+            metrics("foo::run::<u16>::{closure#0}", "_R5", Some(20)),
+        ];
+        let functions = find_generic_bloat(&metrics);
+        insta::assert_snapshot!(functions.iter().map(|function| format!("{}: {} instantiations, {} instructions", function.name, function.instantiations.len(), function.total_instructions)).collect::<Vec<_>>().join("\n"), @r###"
+        foo::parse: 2 instantiations, 100 instructions
+        core::ptr::drop_in_place: 3 instantiations, 35 instructions
+        "###);
+        assert_eq!(
+            functions[0].instantiations[0].function.name,
+            "foo::parse::<u16>"
+        );
+    }
+
+    fn metrics(name: &str, mangled_name: &str, instruction_count: Option<u64>) -> FunctionMetrics {
+        FunctionMetrics {
+            function: Function {
+                name: name.to_string(),
+                mangled_name: Some(mangled_name.to_string()),
+                location: None,
+            },
+            stack_size: None,
+            instruction_count,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Discovery of Cargo configuration files (`.cargo/config.toml`).
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+/// Returns the Cargo home directory (`CARGO_HOME`, or `~/.cargo` if it is not set).
+pub fn cargo_home() -> Option<PathBuf> {
+    match std::env::var_os("CARGO_HOME") {
+        Some(home) => Some(PathBuf::from(home)),
+        None => std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".cargo")),
+    }
+}
+
+/// Finds Cargo configuration files that are used by Cargo when executed in `dir`.
+/// The files are ordered by precedence: files in `dir` come first, then files in its ancestors and
+/// finally the file in the Cargo home directory.
+pub fn find_config_files(dir: &Path) -> Vec<PathBuf> {
+    let mut directories: Vec<PathBuf> = dir.ancestors().map(|dir| dir.join(".cargo")).collect();
+    if let Some(home) = cargo_home() {
+        if !directories.contains(&home) {
+            directories.push(home);
+        }
+    }
+
+    directories
+        .into_iter()
+        .flat_map(|dir| [dir.join("config.toml"), dir.join("config")])
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Loads a TOML file (e.g. a manifest or a Cargo configuration file).
+pub fn load_toml(path: &Path) -> anyhow::Result<toml::Table> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Cannot parse {}", path.display()))
+}
@@ -0,0 +1,596 @@
+//! Building crates with Cargo, while configuring `rustc` to generate optimization remarks.
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+
+use anyhow::Context;
+use cargo_metadata::diagnostic::DiagnosticLevel;
+use cargo_metadata::{Message, Metadata};
+
+use crate::build::profile::Profile;
+use crate::build::toolchain::Toolchain;
+use crate::build::version::check_remark_dir_support;
+use crate::remark::text::{is_remark_diagnostic, load_remarks_from_diagnostics};
+use crate::remark::{load_remarks_from_dir, Remark, RemarkLoadOptions, RemarkStage};
+use crate::utils::callback::LoadCallback;
+use crate::utils::cli::cli_format_path;
+use crate::utils::io::ensure_directory;
+use crate::RustcSourceRoot;
+
+pub mod config;
+pub mod profile;
+pub mod toolchain;
+pub mod version;
+
+pub enum CargoSubcommand {
+    /// Execute `cargo build`.
+    Build,
+    /// Execute an arbitrary Cargo command passed in the extra arguments.
+    Wrap,
+}
+
+/// Determines how will `rustc` output the generated remarks.
+pub enum RemarkMode {
+    /// Store remarks into YAML files using `-Zremark-dir`.
+    /// Requires a nightly compiler.
+    RemarkDir,
+    /// Print remarks as text diagnostics using only `-Cremark`.
+    /// Works on stable, but the remarks contain less information.
+    Diagnostics,
+}
+
+impl RemarkMode {
+    /// Selects the best mode supported by the given `toolchain`.
+    /// Returns an error if the compiler of the toolchain cannot be executed.
+    pub fn detect(toolchain: &Toolchain) -> anyhow::Result<Self> {
+        let supported = check_remark_dir_support(toolchain)
+            .context("Cannot check support for `-Zremark-dir`")?;
+        Ok(if supported {
+            Self::RemarkDir
+        } else {
+            Self::Diagnostics
+        })
+    }
+}
+
+pub enum RemarkOutput {
+    /// Directory containing YAML remark files.
+    YamlDir(PathBuf),
+    /// Text diagnostics containing remarks.
+    Diagnostics(Vec<String>),
+}
+
+pub struct BuildOutput {
+    /// Directory into which should the remark website be rendered.
+    pub web_dir: PathBuf,
+    /// Root directory of the built workspace.
+    pub source_dir: PathBuf,
+    pub remarks: RemarkOutput,
+    /// Metadata of the built workspace.
+    pub metadata: Metadata,
+}
+
+impl BuildOutput {
+    /// Returns the directory with YAML remark files, if remarks were stored into files.
+    pub fn yaml_dir(&self) -> Option<&Path> {
+        match self.remarks {
+            RemarkOutput::YamlDir(ref dir) => Some(dir),
+            RemarkOutput::Diagnostics(_) => None,
+        }
+    }
+
+    /// Loads the remarks generated by the build.
+    pub fn load_remarks(
+        &self,
+        options: RemarkLoadOptions,
+        callback: Option<&(dyn LoadCallback + Send + Sync)>,
+    ) -> anyhow::Result<Vec<Remark>> {
+        match self.remarks {
+            RemarkOutput::YamlDir(ref dir) => load_remarks_from_dir(dir, options, callback),
+            RemarkOutput::Diagnostics(ref messages) => Ok(load_remarks_from_diagnostics(
+                messages.iter().map(|message| message.as_str()),
+                &options,
+            )),
+        }
+    }
+}
+
+/// Configures and executes a Cargo build that generates optimization remarks.
+///
+/// ```no_run
+/// use cargo_remark::build::RemarkBuilder;
+///
+/// let output = RemarkBuilder::new()
+///     .profile("bench")
+///     .package("foo")
+///     .passes(["inline", "loop-vectorize"])
+///     .build()?;
+/// # anyhow::Ok(())
+/// ```
+pub struct RemarkBuilder {
+    subcommand: CargoSubcommand,
+    manifest_path: Option<PathBuf>,
+    target_dir: Option<PathBuf>,
+    profile: Option<String>,
+    packages: Vec<String>,
+    passes: Vec<String>,
+    cargo_args: Vec<String>,
+    toolchain: Toolchain,
+    mode: Option<RemarkMode>,
+}
+
+impl Default for RemarkBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RemarkBuilder {
+    /// Creates a builder that will execute `cargo build` (in the `release` profile by default).
+    pub fn new() -> Self {
+        Self {
+            subcommand: CargoSubcommand::Build,
+            manifest_path: None,
+            target_dir: None,
+            profile: None,
+            packages: vec![],
+            passes: vec![],
+            cargo_args: vec![],
+            toolchain: Toolchain::default(),
+            mode: None,
+        }
+    }
+
+    /// Creates a builder that will execute an arbitrary Cargo command, which is passed as the
+    /// first of the Cargo arguments (e.g. `["test", "--release"]`).
+    /// The profile and packages are not passed to the wrapped command.
+    pub fn wrap() -> Self {
+        Self {
+            subcommand: CargoSubcommand::Wrap,
+            ..Self::new()
+        }
+    }
+
+    /// Path to `Cargo.toml` of the crate or workspace that should be built.
+    /// By default, the crate is found from the current directory.
+    pub fn manifest_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.manifest_path = Some(path.into());
+        self
+    }
+
+    /// Cargo target directory. Remarks will be stored in its `remarks` subdirectory.
+    pub fn target_dir<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.target_dir = Some(path.into());
+        self
+    }
+
+    /// Cargo profile used for the build. The `release` profile is used by default.
+    pub fn profile<S: Into<String>>(mut self, profile: S) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Adds a package that should be built.
+    pub fn package<S: Into<String>>(mut self, package: S) -> Self {
+        self.packages.push(package.into());
+        self
+    }
+
+    /// LLVM passes whose remarks should be generated. Remarks of all passes are generated by default.
+    pub fn passes<I: IntoIterator<Item = S>, S: Into<String>>(mut self, passes: I) -> Self {
+        self.passes
+            .extend(passes.into_iter().map(|pass| pass.into()));
+        self
+    }
+
+    /// Additional arguments that will be passed to Cargo.
+    pub fn cargo_args<I: IntoIterator<Item = S>, S: Into<String>>(mut self, args: I) -> Self {
+        self.cargo_args
+            .extend(args.into_iter().map(|arg| arg.into()));
+        self
+    }
+
+    /// Rust toolchain used to execute Cargo and `rustc`. By default, rustup selects the toolchain.
+    pub fn toolchain(mut self, toolchain: Toolchain) -> Self {
+        self.toolchain = toolchain;
+        self
+    }
+
+    /// Selects how will the remarks be generated. By default, the best mode supported by the
+    /// toolchain is used.
+    pub fn mode(mut self, mode: RemarkMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Executes the build.
+    pub fn build(self) -> anyhow::Result<BuildOutput> {
+        let RemarkBuilder {
+            subcommand,
+            manifest_path,
+            target_dir,
+            profile,
+            packages,
+            passes,
+            cargo_args,
+            toolchain,
+            mode,
+        } = self;
+
+        let ctx = get_cargo_ctx(manifest_path.as_deref(), target_dir)?;
+        let remark_dir = ctx.get_target_directory(Path::new("remarks"))?;
+        let mode = match mode {
+            Some(mode) => mode,
+            None => RemarkMode::detect(&toolchain)?,
+        };
+
+        let yaml_dir = match mode {
+            RemarkMode::RemarkDir => {
+                let yaml_dir = ensure_directory(&remark_dir.join("yaml"))?;
+                log::info!(
+                    "Optimization remarks will be stored into {}.",
+                    cli_format_path(&yaml_dir)
+                );
+                Some(yaml_dir)
+            }
+            RemarkMode::Diagnostics => None,
+        };
+
+        let profile_name = match subcommand {
+            CargoSubcommand::Build => match profile {
+                Some(ref profile) => profile.as_str(),
+                None if find_profile_arg(&cargo_args).is_some() => get_profile_name(&cargo_args),
+                None => "release",
+            },
+            CargoSubcommand::Wrap => get_profile_name(&cargo_args),
+        };
+        let profile_settings = match Profile::load(&ctx.root_directory, profile_name) {
+            Ok(profile) => Some(profile),
+            Err(error) => {
+                log::warn!("Cannot load settings of profile `{profile_name}`: {error:?}");
+                None
+            }
+        };
+
+        let mut cmd = match subcommand {
+            CargoSubcommand::Build => {
+                let cargo_args = parse_cargo_args(cargo_args);
+                let mut cargo = toolchain.cargo();
+                cargo.arg("build").stdin(Stdio::null());
+                match profile {
+                    Some(ref profile) => {
+                        cargo.arg("--profile").arg(profile);
+                    }
+                    // The profile was passed in the Cargo arguments
+                    None if cargo_args.has_profile => {}
+                    None => {
+                        cargo.arg("--release");
+                    }
+                };
+                if let Some(ref manifest_path) = manifest_path {
+                    cargo.arg("--manifest-path").arg(manifest_path);
+                }
+                for package in &packages {
+                    cargo.arg("--package").arg(package);
+                }
+                if let RemarkMode::Diagnostics = mode {
+                    cargo.arg(DIAGNOSTICS_MESSAGE_FORMAT);
+                }
+                cargo.args(cargo_args.filtered);
+                cargo
+            }
+            CargoSubcommand::Wrap => {
+                if cargo_args.is_empty() {
+                    return Err(anyhow::anyhow!("You have to enter a command after `--` that will be executed when using `wrap`."));
+                };
+
+                let mut cmd = toolchain.cargo();
+                cmd.arg(&cargo_args[0]).stdin(Stdio::null());
+                if let RemarkMode::Diagnostics = mode {
+                    // The message format has to be passed after the cargo subcommand
+                    cmd.arg(DIAGNOSTICS_MESSAGE_FORMAT);
+                }
+                cmd.args(&cargo_args[1..]);
+                cmd
+            }
+        };
+        // The target directory is passed through the environment, so that it also works with
+        // wrapped commands.
+        cmd.env("CARGO_TARGET_DIR", &ctx.target_directory);
+        if let Some(ref manifest_path) = manifest_path {
+            if let Some(dir) = manifest_path.parent().filter(|dir| dir.is_dir()) {
+                cmd.current_dir(dir);
+            }
+        }
+
+        let remark_flag = if passes.is_empty() {
+            "-Cremark=all".to_string()
+        } else {
+            format!("-Cremark={}", passes.join(","))
+        };
+        // Use CARGO_ENCODED_RUSTFLAGS to make sure that paths with spaces work.
+        let flags = match yaml_dir {
+            Some(ref yaml_dir) => format!(
+                "{remark_flag}\u{001f}-Zremark-dir={}\u{001f}-Cdebuginfo=1",
+                yaml_dir.display()
+            ),
+            None => format!("{remark_flag}\u{001f}-Cdebuginfo=1"),
+        };
+        set_cargo_env(&mut cmd, &flags);
+
+        let remarks = match yaml_dir {
+            Some(yaml_dir) => {
+                let status = cmd
+                    .spawn()
+                    .map_err(|error| anyhow::anyhow!("Cannot start cargo: {error:?}"))?
+                    .wait()
+                    .map_err(|error| anyhow::anyhow!("Cargo failed: {error:?}"))?;
+                check_cargo_status(status)?;
+
+                if let Some(profile) = profile_settings.filter(|profile| profile.lto_enabled()) {
+                    check_lto_remarks(&yaml_dir, &profile)?;
+                }
+                RemarkOutput::YamlDir(yaml_dir)
+            }
+            None => RemarkOutput::Diagnostics(run_cargo_with_diagnostics(cmd)?),
+        };
+
+        log::info!("Optimization remarks sucessfully generated");
+
+        let web_dir = ensure_directory(&remark_dir.join("web"))?;
+        Ok(BuildOutput {
+            web_dir,
+            source_dir: ctx.root_directory,
+            remarks,
+            metadata: ctx.metadata,
+        })
+    }
+}
+
+pub fn get_rustc_source_root(toolchain: &Toolchain) -> anyhow::Result<RustcSourceRoot> {
+    let mut cmd = toolchain.rustc();
+    cmd.arg("--print").arg("sysroot");
+    if let Some(ref target) = toolchain.target {
+        cmd.arg("--target").arg(target);
+    }
+    let output = cmd.output().context("Cannot get sysroot from `rustc`")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Cannot get sysroot from `rustc`: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let sysroot = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    RustcSourceRoot::from_sysroot(sysroot)
+}
+
+/// Message format used to receive remark diagnostics from Cargo.
+const DIAGNOSTICS_MESSAGE_FORMAT: &str = "--message-format=json-diagnostic-rendered-ansi";
+
+/// Runs Cargo configured to output JSON messages, and gathers remark diagnostics from them.
+/// Other diagnostics and text lines are printed to stderr, so that stdout is left for the output
+/// of `cargo remark`.
+fn run_cargo_with_diagnostics(mut cmd: Command) -> anyhow::Result<Vec<String>> {
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|error| anyhow::anyhow!("Cannot start cargo: {error:?}"))?;
+    let stdout = child.stdout.take().expect("Cargo stdout is not piped");
+
+    let mut remarks = vec![];
+    for message in Message::parse_stream(BufReader::new(stdout)) {
+        match message.context("Cannot read Cargo message")? {
+            Message::CompilerMessage(message) => {
+                let diagnostic = message.message;
+                if diagnostic.level == DiagnosticLevel::Note
+                    && is_remark_diagnostic(&diagnostic.message)
+                {
+                    remarks.push(diagnostic.message);
+                } else if let Some(rendered) = diagnostic.rendered {
+                    eprint!("{rendered}");
+                }
+            }
+            Message::TextLine(line) => eprintln!("{line}"),
+            _ => {}
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|error| anyhow::anyhow!("Cargo failed: {error:?}"))?;
+    check_cargo_status(status)?;
+    Ok(remarks)
+}
+
+fn check_cargo_status(status: ExitStatus) -> anyhow::Result<()> {
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Cargo build failed: exit code {}",
+            status.code().unwrap_or(1)
+        ));
+    }
+    Ok(())
+}
+
+/// Warns the user if LTO is enabled, but no remarks from the LTO pipeline were generated.
+/// In that case, remarks of optimizations performed at link-time (e.g. cross-crate inlining)
+/// will be missing.
+/// Returns true if LTO remarks were found.
+fn check_lto_remarks(yaml_dir: &Path, profile: &Profile) -> anyhow::Result<bool> {
+    let lto_files = std::fs::read_dir(yaml_dir)
+        .with_context(|| format!("Cannot read remark directory {}", yaml_dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| RemarkStage::from_path(&entry.path()) == RemarkStage::Lto)
+        .count();
+    if lto_files == 0 {
+        log::warn!(
+            "LTO is enabled in profile `{}`, but no LTO remarks were generated. Remarks of link-time optimizations will be missing.",
+            profile.name
+        );
+    } else {
+        log::debug!("Found {lto_files} LTO remark file(s)");
+    }
+    Ok(lto_files > 0)
+}
+
+/// Finds out which profile will be used by a wrapped Cargo command.
+fn get_profile_name(cargo_args: &[String]) -> &str {
+    let mut args = cargo_args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => break,
+            "--release" | "-r" => return "release",
+            "--profile" => {
+                if let Some(profile) = args.next() {
+                    return profile;
+                }
+            }
+            _ => {
+                if let Some(profile) = arg.strip_prefix("--profile=") {
+                    return profile;
+                }
+            }
+        }
+    }
+    "dev"
+}
+
+/// Finds the `--profile` argument in the passed Cargo arguments.
+fn find_profile_arg(cargo_args: &[String]) -> Option<&str> {
+    let mut args = cargo_args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => break,
+            "--profile" => return args.next().map(|profile| profile.as_str()),
+            _ => {
+                if let Some(profile) = arg.strip_prefix("--profile=") {
+                    return Some(profile);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn set_cargo_env(command: &mut Command, flags: &str) {
+    let mut rustflags = std::env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default();
+    if !rustflags.is_empty() {
+        rustflags.push('\u{001f}');
+    }
+    rustflags.push_str(flags);
+
+    command.env("CARGO_ENCODED_RUSTFLAGS", rustflags);
+}
+
+#[derive(Debug, Default)]
+struct CargoArgs {
+    filtered: Vec<String>,
+    /// True if a profile was selected with `--profile`, in which case `--release` is not passed.
+    has_profile: bool,
+}
+
+fn parse_cargo_args(cargo_args: Vec<String>) -> CargoArgs {
+    let mut args = CargoArgs {
+        has_profile: find_profile_arg(&cargo_args).is_some(),
+        ..Default::default()
+    };
+
+    for arg in cargo_args {
+        match arg.as_str() {
+            // Skip `--release`, we will pass it by ourselves.
+            "--release" => {
+                log::warn!("Do not pass `--release` manually, it will be added automatically by `cargo-remark`");
+            }
+            _ => args.filtered.push(arg),
+        }
+    }
+    args
+}
+
+pub struct CargoContext {
+    pub target_directory: PathBuf,
+    pub root_directory: PathBuf,
+    pub metadata: Metadata,
+}
+
+impl CargoContext {
+    fn get_target_directory(&self, path: &Path) -> anyhow::Result<PathBuf> {
+        let directory = self.target_directory.join(path);
+        ensure_directory(&directory)?;
+        Ok(directory)
+    }
+}
+
+/// Finds Cargo metadata from the given manifest path, or from the current directory.
+/// If `target_dir` is set, it overrides the target directory of the workspace.
+pub fn get_cargo_ctx(
+    manifest_path: Option<&Path>,
+    target_dir: Option<PathBuf>,
+) -> anyhow::Result<CargoContext> {
+    let mut cmd = cargo_metadata::MetadataCommand::new();
+    if let Some(manifest_path) = manifest_path {
+        cmd.manifest_path(manifest_path);
+    }
+    let metadata = cmd
+        .exec()
+        .map_err(|error| anyhow::anyhow!("Cannot get cargo metadata: {:?}", error))?;
+    Ok(CargoContext {
+        target_directory: target_dir
+            .unwrap_or_else(|| metadata.target_directory.clone().into_std_path_buf()),
+        root_directory: metadata.workspace_root.clone().into_std_path_buf(),
+        metadata,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::profile::Profile;
+    use crate::build::{check_lto_remarks, get_profile_name, parse_cargo_args};
+
+    #[test]
+    fn profile_name_of_wrapped_command() {
+        let name = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            get_profile_name(&args).to_string()
+        };
+        assert_eq!(name(&["test"]), "dev");
+        assert_eq!(name(&["test", "--release"]), "release");
+        assert_eq!(name(&["bench", "-r"]), "release");
+        assert_eq!(name(&["run", "--profile", "profiling"]), "profiling");
+        assert_eq!(name(&["run", "--profile=profiling"]), "profiling");
+        assert_eq!(name(&["run", "--", "--release"]), "dev");
+    }
+
+    #[test]
+    fn cargo_args_with_profile() {
+        let parse =
+            |args: &[&str]| parse_cargo_args(args.iter().map(|arg| arg.to_string()).collect());
+
+        let args = parse(&["--release", "--features", "foo"]);
+        assert_eq!(args.filtered, vec!["--features", "foo"]);
+        assert!(!args.has_profile);
+
+        assert!(parse(&["--profile", "profiling"]).has_profile);
+        assert!(parse(&["--profile=profiling"]).has_profile);
+        assert!(!parse(&["--", "--profile", "profiling"]).has_profile);
+    }
+
+    #[test]
+    fn lto_remarks_found() -> anyhow::Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let profile = Profile {
+            name: "release".to_string(),
+            lto: Some(toml::Value::Boolean(true)),
+            debug: None,
+            strip: None,
+            defined: true,
+        };
+
+        std::fs::write(dir.path().join("foo.a1b2-cgu.0.opt.opt.yaml"), "")?;
+        assert!(!check_lto_remarks(dir.path(), &profile)?);
+
+        std::fs::write(dir.path().join("foo.a1b2-cgu.0.lto.opt.yaml"), "")?;
+        assert!(check_lto_remarks(dir.path(), &profile)?);
+        Ok(())
+    }
+}
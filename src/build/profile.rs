@@ -0,0 +1,194 @@
+use std::path::Path;
+
+use crate::build::config::{find_config_files, load_toml};
+
+/// Subset of Cargo profile settings that affect remark generation.
+pub struct Profile {
+    pub name: String,
+    pub lto: Option<toml::Value>,
+    pub debug: Option<toml::Value>,
+    pub strip: Option<toml::Value>,
+    /// True if the profile is built-in or if it is defined in the manifest or in a Cargo
+    /// configuration file.
+    pub defined: bool,
+}
+
+impl Profile {
+    /// Loads the settings of the profile with the given `name` from the
+    /// `CARGO_PROFILE_<name>_*` environment variables, from Cargo configuration files and from
+    /// the workspace manifest, in this order of precedence.
+    pub fn load(workspace_root: &Path, name: &str) -> anyhow::Result<Self> {
+        let manifest = load_toml(&workspace_root.join("Cargo.toml"))?;
+        let configs: Vec<toml::Table> = find_config_files(workspace_root)
+            .into_iter()
+            .filter_map(|path| match load_toml(&path) {
+                Ok(config) => Some(config),
+                Err(error) => {
+                    log::warn!("Cannot load Cargo configuration: {error:?}");
+                    None
+                }
+            })
+            .collect();
+
+        let mut sources = configs.iter().collect::<Vec<_>>();
+        sources.push(&manifest);
+        Ok(Self::from_sources(name, &sources))
+    }
+
+    /// Finds the settings of the profile in `sources`, which are tables with a `profile` section
+    /// ordered by precedence.
+    fn from_sources(name: &str, sources: &[&toml::Table]) -> Self {
+        Self {
+            name: name.to_string(),
+            lto: get_profile_value(sources, name, "lto"),
+            debug: get_profile_value(sources, name, "debug"),
+            strip: get_profile_value(sources, name, "strip"),
+            defined: matches!(name, "dev" | "release")
+                || builtin_parent(name).is_some()
+                || sources.iter().any(|source| {
+                    source
+                        .get("profile")
+                        .and_then(|profiles| profiles.get(name))
+                        .is_some()
+                }),
+        }
+    }
+
+    /// Returns true if the profile enables fat or thin LTO.
+    pub fn lto_enabled(&self) -> bool {
+        match &self.lto {
+            Some(toml::Value::Boolean(value)) => *value,
+            Some(toml::Value::String(value)) => matches!(value.as_str(), "true" | "fat" | "thin"),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the profile strips debuginfo or symbols from the final artifact.
+    pub fn strips_debuginfo(&self) -> bool {
+        match &self.strip {
+            Some(toml::Value::Boolean(value)) => *value,
+            Some(toml::Value::String(value)) => {
+                matches!(value.as_str(), "true" | "debuginfo" | "symbols")
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if the profile explicitly disables debuginfo.
+    pub fn debuginfo_disabled(&self) -> bool {
+        match &self.debug {
+            Some(toml::Value::Boolean(value)) => !*value,
+            Some(toml::Value::Integer(value)) => *value == 0,
+            Some(toml::Value::String(value)) => matches!(value.as_str(), "0" | "false" | "none"),
+            _ => false,
+        }
+    }
+}
+
+/// Finds the value of a profile `key`, while taking environment overrides and profile
+/// inheritance into account.
+fn get_profile_value(sources: &[&toml::Table], profile: &str, key: &str) -> Option<toml::Value> {
+    let env_name = format!(
+        "CARGO_PROFILE_{}_{}",
+        profile.to_uppercase().replace('-', "_"),
+        key.to_uppercase().replace('-', "_")
+    );
+    if let Ok(value) = std::env::var(env_name) {
+        return Some(toml::Value::String(value));
+    }
+
+    let tables: Vec<&toml::Table> = sources
+        .iter()
+        .filter_map(|source| {
+            source
+                .get("profile")
+                .and_then(|profiles| profiles.get(profile))
+                .and_then(|profile| profile.as_table())
+        })
+        .collect();
+    if let Some(value) = tables.iter().find_map(|table| table.get(key)) {
+        return Some(value.clone());
+    }
+    let parent = tables
+        .iter()
+        .find_map(|table| table.get("inherits"))
+        .and_then(|parent| parent.as_str())
+        .or_else(|| builtin_parent(profile));
+    match parent {
+        Some(parent) if parent != profile => get_profile_value(sources, parent, key),
+        _ => None,
+    }
+}
+
+/// Built-in profiles inherit from `dev` or `release` implicitly.
+fn builtin_parent(profile: &str) -> Option<&'static str> {
+    match profile {
+        "test" => Some("dev"),
+        "bench" => Some("release"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::profile::Profile;
+
+    #[test]
+    fn config_overrides_manifest() {
+        let manifest = table(
+            r#"
+[profile.release]
+lto = "thin"
+debug = 0
+"#,
+        );
+        let config = table(
+            r#"
+[profile.release]
+lto = false
+"#,
+        );
+        let profile = Profile::from_sources("release", &[&config, &manifest]);
+        assert!(!profile.lto_enabled());
+        assert!(profile.debuginfo_disabled());
+    }
+
+    #[test]
+    fn builtin_inheritance() {
+        let manifest = table(
+            r#"
+[profile.release]
+lto = "fat"
+
+[profile.dev]
+debug = false
+"#,
+        );
+        assert!(Profile::from_sources("bench", &[&manifest]).lto_enabled());
+        assert!(Profile::from_sources("test", &[&manifest]).debuginfo_disabled());
+        assert!(!Profile::from_sources("test", &[&manifest]).lto_enabled());
+    }
+
+    #[test]
+    fn custom_inheritance() {
+        let manifest = table(
+            r#"
+[profile.release]
+lto = true
+
+[profile.profiling]
+inherits = "release"
+strip = "none"
+"#,
+        );
+        let profile = Profile::from_sources("profiling", &[&manifest]);
+        assert!(profile.lto_enabled());
+        assert!(profile.defined);
+        assert!(!profile.strips_debuginfo());
+        assert!(!Profile::from_sources("unknown", &[&manifest]).defined);
+    }
+
+    fn table(content: &str) -> toml::Table {
+        toml::from_str(content).unwrap()
+    }
+}
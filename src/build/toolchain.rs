@@ -0,0 +1,91 @@
+use std::process::Command;
+
+/// Rust toolchain that is used to compile the crate.
+#[derive(Debug, Default)]
+pub struct Toolchain {
+    /// Name of a rustup toolchain (e.g. `nightly`), which is passed to rustup proxies as
+    /// `+<name>`. If it is not set, rustup resolves the toolchain by itself, which takes
+    /// `RUSTUP_TOOLCHAIN` and `rust-toolchain.toml` into account.
+    pub name: Option<String>,
+    /// Target triple for which is the crate compiled.
+    pub target: Option<String>,
+}
+
+impl Toolchain {
+    pub fn new(name: Option<String>, cargo_args: &[String]) -> Self {
+        let target = find_target(cargo_args).or_else(|| std::env::var("CARGO_BUILD_TARGET").ok());
+        Self { name, target }
+    }
+
+    /// Creates a command that will execute Cargo from this toolchain.
+    pub fn cargo(&self) -> Command {
+        let mut cmd = Command::new("cargo");
+        if let Some(ref name) = self.name {
+            cmd.arg(format!("+{name}"));
+        }
+        cmd
+    }
+
+    /// Creates a command that will execute the same `rustc` that Cargo will use.
+    /// The `RUSTC` environment variable has precedence over the toolchain name, same as in Cargo.
+    pub fn rustc(&self) -> Command {
+        match std::env::var_os("RUSTC") {
+            Some(rustc) => Command::new(rustc),
+            None => {
+                let mut cmd = Command::new("rustc");
+                if let Some(ref name) = self.name {
+                    cmd.arg(format!("+{name}"));
+                }
+                cmd
+            }
+        }
+    }
+}
+
+/// Finds the `--target` argument in the passed Cargo arguments.
+fn find_target(cargo_args: &[String]) -> Option<String> {
+    let mut args = cargo_args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => break,
+            "--target" => return args.next().cloned(),
+            _ => {
+                if let Some(target) = arg.strip_prefix("--target=") {
+                    return Some(target.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::toolchain::find_target;
+
+    #[test]
+    fn target_argument() {
+        assert_eq!(
+            find(&["--release", "--target", "wasm32-unknown-unknown"]),
+            Some("wasm32-unknown-unknown".to_string())
+        );
+        assert_eq!(
+            find(&["--target=aarch64-unknown-linux-gnu", "--release"]),
+            Some("aarch64-unknown-linux-gnu".to_string())
+        );
+    }
+
+    #[test]
+    fn target_missing() {
+        assert_eq!(find(&[]), None);
+        assert_eq!(find(&["--release"]), None);
+        assert_eq!(find(&["--target"]), None);
+        // Arguments after `--` are passed to the executed binary
+        assert_eq!(find(&["--", "--target", "x86_64-pc-windows-msvc"]), None);
+    }
+
+    fn find(args: &[&str]) -> Option<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        find_target(&args)
+    }
+}
@@ -0,0 +1,60 @@
+use crate::build::toolchain::Toolchain;
+
+/// Returns true if the rustc of the given `toolchain` supports `-Zremark-dir`.
+/// Returns an error if rustc cannot be executed.
+pub fn check_remark_dir_support(toolchain: &Toolchain) -> anyhow::Result<bool> {
+    // Make sure that rustc works at all, because `-Z help` fails also on stable compilers
+    get_rustc_version(toolchain)?;
+
+    let output = toolchain.rustc().arg("-Z").arg("help").output()?;
+    if !output.status.success() {
+        log::debug!(
+            "rustc -Z help failed, the compiler is probably not nightly: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return Ok(false);
+    }
+
+    let options = String::from_utf8_lossy(&output.stdout);
+    for line in options.lines() {
+        let items: Vec<&str> = line.split_whitespace().take(2).map(|v| v.trim()).collect();
+        if items.len() != 2 || items[0] != "-Z" {
+            continue;
+        }
+        if items[1] == "remark-dir=val" {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+pub struct RustcVersion {
+    /// Version line printed by `rustc -V`.
+    pub version: String,
+    /// Release channel of the compiler (e.g. `stable` or `nightly`).
+    pub channel: String,
+}
+
+/// Finds out the version of rustc of the given `toolchain`.
+pub fn get_rustc_version(toolchain: &Toolchain) -> anyhow::Result<RustcVersion> {
+    let output = toolchain.rustc().arg("-vV").output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to execute rustc -vV: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let output = String::from_utf8_lossy(&output.stdout);
+    let version = output.lines().next().unwrap_or_default().to_string();
+    let channel = output
+        .lines()
+        .find_map(|line| line.strip_prefix("release: "))
+        .map(|release| match release.split_once('-') {
+            Some((_, channel)) => channel.to_string(),
+            None => "stable".to_string(),
+        })
+        .unwrap_or_default();
+    Ok(RustcVersion { version, channel })
+}
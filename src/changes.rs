@@ -0,0 +1,215 @@
+//! Filtering of remarks by source lines that were changed since a git revision.
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Context;
+
+use crate::annotation::suppress::SuppressedRemark;
+use crate::remark::{Line, Location, MessagePart, Remark};
+use crate::utils::data_structures::Map;
+
+/// Lines that were changed in the working tree since some git revision.
+#[derive(Debug, Default)]
+pub struct ChangedLines {
+    /// Inclusive ranges of changed lines, keyed by paths relative to the source directory.
+    files: Map<String, Vec<(Line, Line)>>,
+}
+
+impl ChangedLines {
+    /// Finds lines changed since `revision` by running `git diff` in `source_dir`.
+    /// Files that are not tracked by git (and not ignored) are considered to be changed as a whole.
+    pub fn from_git(source_dir: &Path, revision: &str) -> anyhow::Result<Self> {
+        let diff = run_git(
+            source_dir,
+            &[
+                "-c",
+                "core.quotePath=false",
+                "diff",
+                "--unified=0",
+                "--relative",
+                "--no-prefix",
+                "--no-color",
+                "--no-ext-diff",
+                revision,
+                "--",
+            ],
+        )?;
+        let mut changes = Self::parse_diff(&diff);
+
+        let untracked = run_git(source_dir, &["ls-files", "--others", "--exclude-standard"])?;
+        for file in untracked.lines().filter(|file| !file.is_empty()) {
+            changes.files.insert(file.to_string(), vec![(1, Line::MAX)]);
+        }
+        Ok(changes)
+    }
+
+    /// Parses the output of `git diff --unified=0 --no-prefix`.
+    pub fn parse_diff(diff: &str) -> Self {
+        let mut files: Map<String, Vec<(Line, Line)>> = Map::default();
+        let mut current_file: Option<String> = None;
+        let mut previous = "";
+        for line in diff.lines() {
+            // Added lines can also start with `+++`, but only the file header follows `---`
+            if let Some(path) = line
+                .strip_prefix("+++ ")
+                .filter(|_| previous.starts_with("--- "))
+            {
+                current_file = (path != "/dev/null").then(|| unquote(path));
+            } else if let Some(hunk) = line.strip_prefix("@@ ") {
+                let Some(file) = &current_file else {
+                    continue;
+                };
+                // Format: @@ -<old>[,<count>] +<start>[,<count>] @@
+                let Some(new_range) = hunk
+                    .split_whitespace()
+                    .find_map(|range| range.strip_prefix('+'))
+                else {
+                    continue;
+                };
+                let (start, count) = match new_range.split_once(',') {
+                    Some((start, count)) => (start.parse::<Line>(), count.parse::<Line>()),
+                    None => (new_range.parse::<Line>(), Ok(1)),
+                };
+                let (Ok(start), Ok(count)) = (start, count) else {
+                    continue;
+                };
+                // Hunks that only remove lines do not contain any new line
+                if count > 0 {
+                    files
+                        .entry(file.clone())
+                        .or_default()
+                        .push((start, start + count - 1));
+                }
+            }
+            previous = line;
+        }
+        Self { files }
+    }
+
+    pub fn contains(&self, location: &Location) -> bool {
+        self.files
+            .get(location.file.as_str())
+            .map(|ranges| {
+                ranges
+                    .iter()
+                    .any(|(start, end)| (*start..=*end).contains(&location.line))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Returns true if the remark or any location mentioned in its message is on a changed line.
+    pub fn is_changed(&self, remark: &Remark) -> bool {
+        remark
+            .function
+            .location
+            .as_ref()
+            .is_some_and(|location| self.contains(location))
+            || remark.message.iter().any(|part| match part {
+                MessagePart::AnnotatedString { location, .. } => self.contains(location),
+                MessagePart::String(_) => false,
+            })
+    }
+
+    /// Keeps only remarks on changed lines.
+    pub fn retain_changed(&self, remarks: &mut Vec<Remark>) {
+        remarks.retain(|remark| self.is_changed(remark));
+    }
+}
+
+/// Keeps only remarks located on lines changed since `revision` in `source_dir`.
+/// Suppressed remarks are filtered as well, if they are given.
+pub fn filter_changed_since(
+    source_dir: &Path,
+    revision: &str,
+    remarks: &mut Vec<Remark>,
+    suppressed: Option<&mut Vec<SuppressedRemark>>,
+) -> anyhow::Result<()> {
+    let changes = ChangedLines::from_git(source_dir, revision)?;
+    changes.retain_changed(remarks);
+    if let Some(suppressed) = suppressed {
+        suppressed.retain(|suppressed| changes.is_changed(&suppressed.remark));
+    }
+    log::info!(
+        "{} remark(s) are located on lines changed since {revision}",
+        remarks.len()
+    );
+    Ok(())
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("Cannot execute git")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "`git {}` has failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Git quotes paths that contain special characters, such as quotes or backslashes.
+fn unquote(path: &str) -> String {
+    match path
+        .strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+    {
+        Some(path) => path.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::changes::ChangedLines;
+    use crate::remark::Location;
+
+    const DIFF: &str = r#"diff --git src/main.rs src/main.rs
+index 1111111..2222222 100644
+--- src/main.rs
++++ src/main.rs
+@@ -3 +3 @@ fn main() {
+-    foo();
++    bar();
+@@ -10,2 +9,0 @@ fn main() {
+-    a();
+-    b();
+@@ -20,0 +20,3 @@ fn baz() {
++    c();
++    d();
++    e();
+diff --git src/old.rs src/old.rs
+deleted file mode 100644
+--- src/old.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-fn old() {}
+"#;
+
+    #[test]
+    fn parse_diff_hunks() {
+        let changes = ChangedLines::parse_diff(DIFF);
+        let changed = |line| {
+            changes.contains(&Location {
+                file: "src/main.rs".to_string(),
+                line,
+                column: 1,
+            })
+        };
+        assert!(changed(3));
+        assert!(!changed(4));
+        assert!(!changed(9));
+        assert!(changed(20));
+        assert!(changed(22));
+        assert!(!changed(23));
+        assert!(!changes.contains(&Location {
+            file: "src/old.rs".to_string(),
+            line: 1,
+            column: 1,
+        }));
+    }
+}
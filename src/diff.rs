@@ -0,0 +1,276 @@
+//! Comparison of remarks generated by two different builds.
+use std::io::Write;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::remark::{load_remarks_from_dir, write_remark, Remark, RemarkLoadOptions};
+use crate::utils::data_structures::Map;
+
+pub struct ChangedRemark {
+    pub old: Remark,
+    pub new: Remark,
+}
+
+/// Difference between remarks of an old and a new build.
+/// Remarks that have not changed are not included.
+#[derive(Default)]
+pub struct RemarkDiff {
+    /// Remarks that are only present in the new build.
+    pub new: Vec<Remark>,
+    /// Remarks that are only present in the old build.
+    pub fixed: Vec<Remark>,
+    /// Remarks that are present in both builds, but their message or hotness has changed.
+    pub changed: Vec<ChangedRemark>,
+}
+
+impl RemarkDiff {
+    pub fn is_empty(&self) -> bool {
+        self.new.is_empty() && self.fixed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Loads remarks from two directories and compares them.
+pub fn diff_remark_dirs<P: AsRef<Path>>(
+    old_dir: P,
+    new_dir: P,
+    options: RemarkLoadOptions,
+) -> anyhow::Result<RemarkDiff> {
+    let old = load_remarks_from_dir(old_dir, options.clone(), None)?;
+    let new = load_remarks_from_dir(new_dir, options, None)?;
+    Ok(diff_remarks(old, new))
+}
+
+/// Remarks are matched if they have the same pass, name, function and file.
+/// Line numbers are not a part of the key, so that remarks are matched even if the code
+/// around them has shifted.
+#[derive(PartialEq, Eq, Hash)]
+struct MatchKey {
+    pass: String,
+    name: String,
+    function: String,
+    file: Option<String>,
+}
+
+impl MatchKey {
+    fn new(remark: &Remark) -> Self {
+        Self {
+            pass: remark.pass.clone(),
+            name: remark.name.clone(),
+            function: remark.function.name.clone(),
+            file: remark.function.location.as_ref().map(|l| l.file.clone()),
+        }
+    }
+}
+
+/// Compares remarks of two builds.
+///
+/// Remarks with the same pass, name, function and file are matched in three rounds:
+/// 1) Remarks with the same position and message.
+/// 2) Remarks with the same message, in the order of their lines.
+/// 3) Remaining remarks, in the order of their lines.
+pub fn diff_remarks(old: Vec<Remark>, new: Vec<Remark>) -> RemarkDiff {
+    let mut groups: Map<MatchKey, (Vec<Remark>, Vec<Remark>)> = Map::default();
+    for remark in old {
+        groups
+            .entry(MatchKey::new(&remark))
+            .or_default()
+            .0
+            .push(remark);
+    }
+    for remark in new {
+        groups
+            .entry(MatchKey::new(&remark))
+            .or_default()
+            .1
+            .push(remark);
+    }
+
+    let mut diff = RemarkDiff::default();
+    for (_, (mut old, mut new)) in groups {
+        old.sort_by_key(position);
+        new.sort_by_key(position);
+
+        let mut pairs = vec![];
+        take_matches(&mut old, &mut new, &mut pairs, |a, b| {
+            position(a) == position(b) && a.message_text() == b.message_text()
+        });
+        take_matches(&mut old, &mut new, &mut pairs, |a, b| {
+            a.message_text() == b.message_text()
+        });
+        take_matches(&mut old, &mut new, &mut pairs, |_, _| true);
+
+        for (old, new) in pairs {
+            if old.message_text() != new.message_text() || old.hotness != new.hotness {
+                diff.changed.push(ChangedRemark { old, new });
+            }
+        }
+        diff.fixed.extend(old);
+        diff.new.extend(new);
+    }
+
+    diff.new.sort_by(compare_remarks);
+    diff.fixed.sort_by(compare_remarks);
+    diff.changed.sort_by(|a, b| compare_remarks(&a.new, &b.new));
+    diff
+}
+
+/// Greedily pairs remarks from `old` and `new` that satisfy the `matches` predicate.
+/// Paired remarks are removed from the input vectors.
+fn take_matches<F: Fn(&Remark, &Remark) -> bool>(
+    old: &mut Vec<Remark>,
+    new: &mut Vec<Remark>,
+    pairs: &mut Vec<(Remark, Remark)>,
+    matches: F,
+) {
+    let mut index = 0;
+    while index < old.len() {
+        match new.iter().position(|remark| matches(&old[index], remark)) {
+            Some(new_index) => {
+                pairs.push((old.remove(index), new.remove(new_index)));
+            }
+            None => index += 1,
+        }
+    }
+}
+
+fn position(remark: &Remark) -> (u32, u32) {
+    remark
+        .function
+        .location
+        .as_ref()
+        .map(|location| (location.line, location.column))
+        .unwrap_or_default()
+}
+
+fn compare_remarks(a: &Remark, b: &Remark) -> std::cmp::Ordering {
+    let location = |remark: &Remark| {
+        remark
+            .function
+            .location
+            .as_ref()
+            .map(|location| (location.file.clone(), location.line, location.column))
+    };
+    location(a)
+        .cmp(&location(b))
+        .then_with(|| a.name.cmp(&b.name))
+}
+
+/// Writes a human-readable summary of the diff.
+pub fn write_diff_text<W: Write>(diff: &RemarkDiff, mut writer: W) -> std::io::Result<()> {
+    for remark in &diff.new {
+        write_remark(&mut writer, "+".green(), remark)?;
+    }
+    for remark in &diff.fixed {
+        write_remark(&mut writer, "-".red(), remark)?;
+    }
+    for ChangedRemark { old, new } in &diff.changed {
+        write_remark(&mut writer, "~".yellow(), new)?;
+        writeln!(writer, "    was: {}", old.message_text())?;
+        if old.hotness != new.hotness {
+            writeln!(
+                writer,
+                "    hotness: {} -> {}",
+                format_hotness(old.hotness),
+                format_hotness(new.hotness)
+            )?;
+        }
+    }
+    writeln!(
+        writer,
+        "{} new, {} fixed, {} changed remark(s)",
+        diff.new.len().to_string().green(),
+        diff.fixed.len().to_string().red(),
+        diff.changed.len().to_string().yellow()
+    )
+}
+
+fn format_hotness(hotness: Option<i32>) -> String {
+    hotness
+        .map(|hotness| hotness.to_string())
+        .unwrap_or_else(|| "none".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::diff_remarks;
+    use crate::remark::fixtures::remark;
+
+    #[test]
+    fn diff_line_shift() {
+        let old = vec![
+            remark("inline", "NoDefinition")
+                .at("src/main.rs", 10)
+                .with_text("foo"),
+            remark("inline", "NoDefinition")
+                .at("src/main.rs", 20)
+                .with_text("bar"),
+        ];
+        let new = vec![
+            remark("inline", "NoDefinition")
+                .at("src/main.rs", 15)
+                .with_text("foo"),
+            remark("inline", "NoDefinition")
+                .at("src/main.rs", 25)
+                .with_text("bar"),
+        ];
+        assert!(diff_remarks(old, new).is_empty());
+    }
+
+    #[test]
+    fn diff_new_and_fixed() {
+        let old = vec![
+            remark("inline", "NoDefinition")
+                .at("src/main.rs", 10)
+                .with_text("foo"),
+            remark("inline", "NoDefinition")
+                .at("src/main.rs", 20)
+                .with_text("bar"),
+        ];
+        let new = vec![
+            remark("inline", "NoDefinition")
+                .at("src/main.rs", 12)
+                .with_text("bar"),
+            remark("inline", "TooCostly")
+                .at("src/main.rs", 30)
+                .with_text("baz"),
+        ];
+        let diff = diff_remarks(old, new);
+        assert_eq!(diff.new.len(), 1);
+        assert_eq!(diff.new[0].name, "TooCostly");
+        assert_eq!(diff.fixed.len(), 1);
+        assert_eq!(diff.fixed[0].message_text(), "foo");
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn diff_changed_message() {
+        let old = vec![remark("inline", "TooCostly")
+            .at("src/main.rs", 10)
+            .with_text("cost=300, threshold=250")];
+        let new = vec![remark("inline", "TooCostly")
+            .at("src/main.rs", 11)
+            .with_text("cost=400, threshold=250")];
+        let diff = diff_remarks(old, new);
+        assert!(diff.new.is_empty());
+        assert!(diff.fixed.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            diff.changed[0].new.message_text(),
+            "cost=400, threshold=250"
+        );
+    }
+
+    #[test]
+    fn diff_changed_hotness() {
+        let old = vec![remark("inline", "TooCostly")
+            .at("src/main.rs", 10)
+            .with_text("foo")];
+        let new = vec![remark("inline", "TooCostly")
+            .at("src/main.rs", 10)
+            .with_text("foo")
+            .with_hotness(5)];
+        let diff = diff_remarks(old, new);
+        assert_eq!(diff.changed.len(), 1);
+    }
+}
@@ -0,0 +1,435 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+
+use cargo_remark::build::config::{find_config_files, load_toml};
+use cargo_remark::build::profile::Profile;
+use cargo_remark::build::toolchain::Toolchain;
+use cargo_remark::build::version::{check_remark_dir_support, get_rustc_version};
+use cargo_remark::build::{get_cargo_ctx, get_rustc_source_root};
+use cargo_remark::utils::cli::cli_format_path;
+
+/// Remark files can be quite large, so we warn if there is less free space than this.
+const MIN_FREE_SPACE: u64 = 1024 * 1024 * 1024;
+
+enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+struct CheckResult {
+    name: &'static str,
+    status: CheckStatus,
+    message: String,
+    /// Suggestion how to fix a warning or a failure.
+    fix: Option<String>,
+}
+
+impl CheckResult {
+    fn pass<M: Display>(name: &'static str, message: M) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            message: message.to_string(),
+            fix: None,
+        }
+    }
+
+    fn warn<M: Display, F: Display>(name: &'static str, message: M, fix: F) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            message: message.to_string(),
+            fix: Some(fix.to_string()),
+        }
+    }
+
+    fn fail<M: Display, F: Display>(name: &'static str, message: M, fix: F) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            message: message.to_string(),
+            fix: Some(fix.to_string()),
+        }
+    }
+}
+
+/// Checks the environment in which are remarks generated, and prints a report with the results.
+/// Returns an error if any of the checks has failed.
+pub fn run_doctor(toolchain: Toolchain, profile: String) -> anyhow::Result<()> {
+    let mut results = vec![];
+    check_toolchain(&toolchain, &mut results);
+    check_rustflags(&mut results);
+
+    match get_cargo_ctx(None, None) {
+        Ok(ctx) => {
+            check_profile(&ctx.root_directory, &profile, &mut results);
+            check_disk_space(&ctx.target_directory, &mut results);
+        }
+        Err(error) => results.push(CheckResult::fail(
+            "Cargo",
+            format!("cannot load Cargo metadata: {error}"),
+            "run `cargo remark doctor` inside a Cargo project",
+        )),
+    }
+
+    let mut failed = 0;
+    for result in results {
+        let status = match result.status {
+            CheckStatus::Pass => "PASS".green(),
+            CheckStatus::Warn => "WARN".yellow(),
+            CheckStatus::Fail => {
+                failed += 1;
+                "FAIL".red()
+            }
+        };
+        println!("[{status}] {}: {}", result.name.bold(), result.message);
+        if let Some(fix) = result.fix {
+            println!("       {} {fix}", "Fix:".cyan());
+        }
+    }
+
+    if failed > 0 {
+        Err(anyhow::anyhow!("{failed} check(s) have failed"))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_toolchain(toolchain: &Toolchain, results: &mut Vec<CheckResult>) {
+    let toolchain_arg = toolchain
+        .name
+        .as_ref()
+        .map(|name| format!(" --toolchain {name}"))
+        .unwrap_or_default();
+
+    match get_rustc_version(toolchain) {
+        Ok(version) if version.channel == "nightly" => {
+            results.push(CheckResult::pass("Toolchain", version.version));
+        }
+        Ok(version) => results.push(CheckResult::warn(
+            "Toolchain",
+            format!(
+                "{} is not a nightly compiler, remarks will be parsed from diagnostics",
+                version.version
+            ),
+            "install a nightly toolchain with `rustup toolchain install nightly` and use `--toolchain nightly`",
+        )),
+        Err(error) => {
+            results.push(CheckResult::fail(
+                "Toolchain",
+                format!("cannot execute rustc: {error}"),
+                "install Rust using rustup or check the `--toolchain` option and the `RUSTC` environment variable",
+            ));
+            return;
+        }
+    }
+
+    match check_remark_dir_support(toolchain) {
+        Ok(true) => results.push(CheckResult::pass("-Zremark-dir", "supported")),
+        Ok(false) | Err(_) => results.push(CheckResult::warn(
+            "-Zremark-dir",
+            "not supported, remarks will not contain remark names, hotness and some function names",
+            "use a nightly compiler newer than 4. 7. 2023",
+        )),
+    }
+
+    match get_rustc_source_root(toolchain) {
+        Ok(root) => results.push(CheckResult::pass(
+            "rust-src",
+            format!("found at {}", cli_format_path(root.0)),
+        )),
+        Err(error) => results.push(CheckResult::warn(
+            "rust-src",
+            format!("cannot find standard library sources ({error}), remarks in the standard library will not be resolved"),
+            format!("run `rustup component add rust-src{toolchain_arg}`"),
+        )),
+    }
+}
+
+fn check_rustflags(results: &mut Vec<CheckResult>) {
+    let encoded_flags = std::env::var("CARGO_ENCODED_RUSTFLAGS").ok();
+    let flags = std::env::var("RUSTFLAGS").ok();
+
+    match (encoded_flags, flags) {
+        (Some(encoded_flags), _) => results.push(CheckResult::pass(
+            "RUSTFLAGS",
+            format!(
+                "`{}` (from CARGO_ENCODED_RUSTFLAGS)",
+                encoded_flags.replace('\u{001f}', " ")
+            ),
+        )),
+        (None, Some(flags)) => results.push(CheckResult::warn(
+            "RUSTFLAGS",
+            format!("`{flags}` will be ignored, because `cargo remark` passes its flags using CARGO_ENCODED_RUSTFLAGS"),
+            "pass the flags using CARGO_ENCODED_RUSTFLAGS (separated by the 0x1f character)",
+        )),
+        (None, None) => {
+            let configs = std::env::current_dir()
+                .map(|dir| find_configs_with_rustflags(&dir))
+                .unwrap_or_default();
+            if configs.is_empty() {
+                results.push(CheckResult::pass("RUSTFLAGS", "no custom flags"));
+            } else {
+                let configs = configs
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                results.push(CheckResult::warn(
+                    "RUSTFLAGS",
+                    format!("rustflags from {configs} will be ignored, because `cargo remark` passes its flags using CARGO_ENCODED_RUSTFLAGS"),
+                    "pass the flags using CARGO_ENCODED_RUSTFLAGS (separated by the 0x1f character)",
+                ));
+            }
+        }
+    }
+}
+
+fn check_profile(workspace_root: &Path, profile: &str, results: &mut Vec<CheckResult>) {
+    let profile = match Profile::load(workspace_root, profile) {
+        Ok(profile) => profile,
+        Err(error) => {
+            results.push(CheckResult::fail(
+                "Profile",
+                format!("cannot load profile `{profile}`: {error:#}"),
+                "make sure that the workspace manifest is valid",
+            ));
+            return;
+        }
+    };
+
+    if !profile.defined {
+        results.push(CheckResult::fail(
+            "Profile",
+            format!("profile `{}` is not defined", profile.name),
+            format!(
+                "add a `[profile.{}]` section to the workspace manifest or use an existing profile",
+                profile.name
+            ),
+        ));
+    }
+
+    if profile.debuginfo_disabled() {
+        results.push(CheckResult::warn(
+            "Debuginfo",
+            format!(
+                "disabled in profile `{}`, `cargo remark` overrides it with `-Cdebuginfo=1`, so the remarks will not exactly match your regular builds",
+                profile.name
+            ),
+            format!(
+                "set `debug = \"line-tables-only\"` in profile `{}`",
+                profile.name
+            ),
+        ));
+    } else {
+        results.push(CheckResult::pass(
+            "Debuginfo",
+            format!("not disabled in profile `{}`", profile.name),
+        ));
+    }
+
+    match profile.strip {
+        Some(ref strip) if profile.strips_debuginfo() => results.push(CheckResult::warn(
+            "Strip",
+            format!("profile `{}` strips debuginfo from the final artifact (strip = {strip}), so profilers will not be able to map samples to remark locations", profile.name),
+            format!("set `strip = \"none\"` in profile `{}` when correlating remarks with profiles", profile.name),
+        )),
+        _ => results.push(CheckResult::pass(
+            "Strip",
+            format!("profile `{}` does not strip debuginfo", profile.name),
+        )),
+    }
+
+    if profile.lto_enabled() {
+        results.push(CheckResult::pass(
+            "LTO",
+            format!(
+                "enabled in profile `{}` (lto = {}), remarks will also be generated by the LTO pipeline",
+                profile.name,
+                profile.lto.as_ref().map(|lto| lto.to_string()).unwrap_or_default()
+            ),
+        ));
+    } else {
+        results.push(CheckResult::pass(
+            "LTO",
+            format!("disabled in profile `{}`", profile.name),
+        ));
+    }
+}
+
+fn check_disk_space(target_dir: &Path, results: &mut Vec<CheckResult>) {
+    // The target directory might not exist yet
+    let dir = target_dir
+        .ancestors()
+        .find(|dir| dir.is_dir())
+        .unwrap_or(target_dir);
+    match fs2::available_space(dir) {
+        Ok(space) if space >= MIN_FREE_SPACE => results.push(CheckResult::pass(
+            "Disk space",
+            format!(
+                "{} MiB available in {}",
+                space / (1024 * 1024),
+                cli_format_path(dir)
+            ),
+        )),
+        Ok(space) => results.push(CheckResult::warn(
+            "Disk space",
+            format!(
+                "only {} MiB available in {}",
+                space / (1024 * 1024),
+                cli_format_path(dir)
+            ),
+            "free some disk space, remark files of large crates can take up gigabytes",
+        )),
+        Err(error) => results.push(CheckResult::warn(
+            "Disk space",
+            format!(
+                "cannot determine available space in {}: {error}",
+                cli_format_path(dir)
+            ),
+            "make sure that the target directory is writable",
+        )),
+    }
+}
+
+/// Finds Cargo configuration files used in `dir` that set rustflags, which are ignored when
+/// `CARGO_ENCODED_RUSTFLAGS` is set.
+fn find_configs_with_rustflags(dir: &Path) -> Vec<PathBuf> {
+    find_config_files(dir)
+        .into_iter()
+        .filter(|path| {
+            let Ok(config) = load_toml(path) else {
+                return false;
+            };
+            let build_flags = config
+                .get("build")
+                .and_then(|build| build.get("rustflags"))
+                .is_some();
+            let target_flags = config
+                .get("target")
+                .and_then(|target| target.as_table())
+                .map(|targets| {
+                    targets
+                        .values()
+                        .any(|target| target.get("rustflags").is_some())
+                })
+                .unwrap_or(false);
+            build_flags || target_flags
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::doctor::{check_profile, find_configs_with_rustflags, CheckResult, CheckStatus};
+
+    #[test]
+    fn profile_ok() -> anyhow::Result<()> {
+        let dir = manifest(
+            r#"
+[profile.release]
+lto = "thin"
+"#,
+        )?;
+        let results = run_check_profile(dir.path(), "release");
+        assert_eq!(
+            statuses(&results),
+            vec![("Debuginfo", "pass"), ("Strip", "pass"), ("LTO", "pass")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn profile_disables_debuginfo() -> anyhow::Result<()> {
+        let dir = manifest(
+            r#"
+[profile.release]
+debug = 0
+strip = "debuginfo"
+"#,
+        )?;
+        let results = run_check_profile(dir.path(), "bench");
+        assert_eq!(
+            statuses(&results),
+            vec![("Debuginfo", "warn"), ("Strip", "warn"), ("LTO", "pass")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn profile_missing() -> anyhow::Result<()> {
+        let dir = manifest("")?;
+        let results = run_check_profile(dir.path(), "profiling");
+        assert_eq!(statuses(&results)[0], ("Profile", "fail"));
+
+        let dir = tempfile::TempDir::new()?;
+        let results = run_check_profile(dir.path(), "release");
+        assert_eq!(statuses(&results), vec![("Profile", "fail")]);
+        Ok(())
+    }
+
+    #[test]
+    fn configs_with_rustflags() -> anyhow::Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let nested = dir.path().join("nested");
+        std::fs::create_dir_all(nested.join(".cargo"))?;
+        std::fs::create_dir_all(dir.path().join(".cargo"))?;
+
+        let build_config = dir.path().join(".cargo").join("config.toml");
+        std::fs::write(
+            &build_config,
+            "[build]\nrustflags = [\"-Ctarget-cpu=native\"]\n",
+        )?;
+        let target_config = nested.join(".cargo").join("config");
+        std::fs::write(
+            &target_config,
+            "[target.x86_64-unknown-linux-gnu]\nrustflags = [\"-Clink-arg=-fuse-ld=lld\"]\n",
+        )?;
+        let other_config = nested.join(".cargo").join("config.toml");
+        std::fs::write(&other_config, "[alias]\nb = \"build\"\n")?;
+
+        let configs = find_configs_with_rustflags(&nested);
+        assert!(configs.contains(&build_config));
+        assert!(configs.contains(&target_config));
+        assert!(!configs.contains(&other_config));
+
+        let configs = find_configs_with_rustflags(dir.path());
+        assert!(configs.contains(&build_config));
+        assert!(!configs.contains(&target_config));
+        Ok(())
+    }
+
+    fn manifest(profiles: &str) -> anyhow::Result<tempfile::TempDir> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            format!("[package]\nname = \"foo\"\nversion = \"0.1.0\"\n{profiles}"),
+        )?;
+        Ok(dir)
+    }
+
+    fn run_check_profile(workspace_root: &Path, profile: &str) -> Vec<CheckResult> {
+        let mut results = vec![];
+        check_profile(workspace_root, profile, &mut results);
+        results
+    }
+
+    fn statuses(results: &[CheckResult]) -> Vec<(&'static str, &'static str)> {
+        results
+            .iter()
+            .map(|result| {
+                let status = match result.status {
+                    CheckStatus::Pass => "pass",
+                    CheckStatus::Warn => "warn",
+                    CheckStatus::Fail => "fail",
+                };
+                (result.name, status)
+            })
+            .collect()
+    }
+}
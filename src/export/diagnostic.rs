@@ -0,0 +1,213 @@
+//! Output of remarks as Cargo JSON messages (`--message-format=json`), which can be consumed by
+//! editors (e.g. by rust-analyzer using a custom check command).
+use std::io::Write;
+use std::path::Path;
+
+use cargo_metadata::{Metadata, Package, PackageId, Target};
+
+use crate::export::text::{write_remark_snippet, SourceCache};
+use crate::remark::{Location, MessagePart, Remark};
+use crate::render::resolve_path;
+
+#[derive(serde::Serialize)]
+struct CompilerMessage<'a> {
+    reason: &'static str,
+    package_id: &'a PackageId,
+    manifest_path: &'a Path,
+    target: &'a Target,
+    message: Diagnostic,
+}
+
+/// Diagnostic in the same format as emitted by `rustc --error-format=json`.
+#[derive(serde::Serialize)]
+struct Diagnostic {
+    #[serde(rename = "$message_type")]
+    message_type: &'static str,
+    message: String,
+    code: Option<DiagnosticCode>,
+    level: &'static str,
+    spans: Vec<DiagnosticSpan>,
+    children: Vec<Diagnostic>,
+    rendered: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct DiagnosticCode {
+    code: String,
+    explanation: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct DiagnosticSpan {
+    file_name: String,
+    byte_start: usize,
+    byte_end: usize,
+    line_start: u32,
+    line_end: u32,
+    column_start: u32,
+    column_end: u32,
+    is_primary: bool,
+    text: Vec<DiagnosticSpanLine>,
+    label: Option<String>,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<String>,
+    expansion: Option<()>,
+}
+
+#[derive(serde::Serialize)]
+struct DiagnosticSpanLine {
+    text: String,
+    highlight_start: u32,
+    highlight_end: u32,
+}
+
+/// Writes the remarks as Cargo `compiler-message` JSON messages with the `note` level.
+///
+/// Each remark is attributed to the workspace package and target that contain its source file.
+/// Remarks without a location and remarks in files outside of the workspace packages (e.g. in the
+/// standard library) are skipped, because editors cannot display them.
+pub fn write_compiler_messages<'r, I: IntoIterator<Item = &'r Remark>, W: Write>(
+    remarks: I,
+    source_dir: &Path,
+    metadata: &Metadata,
+    mut writer: W,
+) -> anyhow::Result<()> {
+    let packages = metadata.workspace_packages();
+    let mut cache = SourceCache::new(source_dir);
+
+    for remark in remarks {
+        let Some(location) = &remark.function.location else {
+            continue;
+        };
+        let path = resolve_path(source_dir, Path::new(&location.file)).into_owned();
+        let Some((package, target)) = find_target(&packages, &path) else {
+            continue;
+        };
+
+        let mut children = vec![];
+        for part in &remark.message {
+            if let MessagePart::AnnotatedString { message, location } = part {
+                children.push(Diagnostic {
+                    message_type: "diagnostic",
+                    message: format!("`{message}` is defined here"),
+                    code: None,
+                    level: "note",
+                    spans: vec![create_span(location, None, &mut cache)],
+                    children: vec![],
+                    rendered: None,
+                });
+            }
+        }
+        if let Some(hotness) = remark.hotness {
+            children.push(Diagnostic {
+                message_type: "diagnostic",
+                message: format!("hotness: {hotness}"),
+                code: None,
+                level: "note",
+                spans: vec![],
+                children: vec![],
+                rendered: None,
+            });
+        }
+
+        // Remarks parsed from text diagnostics use the pass as their name
+        let kind = if remark.name == remark.pass {
+            remark.name.clone()
+        } else {
+            format!("{} ({})", remark.name, remark.pass)
+        };
+        let mut rendered = vec![];
+        write_remark_snippet(&mut rendered, remark, &mut cache, None)?;
+
+        let message = CompilerMessage {
+            reason: "compiler-message",
+            package_id: &package.id,
+            manifest_path: package.manifest_path.as_std_path(),
+            target,
+            message: Diagnostic {
+                message_type: "diagnostic",
+                message: format!("{kind}: {}", remark.message_text()),
+                code: Some(DiagnosticCode {
+                    code: remark.name.clone(),
+                    explanation: None,
+                }),
+                level: "note",
+                spans: vec![create_span(
+                    location,
+                    Some(format!("in `{}`", remark.function.name)),
+                    &mut cache,
+                )],
+                children,
+                rendered: Some(String::from_utf8_lossy(&rendered).into_owned()),
+            },
+        };
+        serde_json::to_writer(&mut writer, &message)?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Finds the package and target whose directories are the closest ancestors of `path`.
+/// Returns `None` for files outside of the workspace packages.
+fn find_target<'a>(packages: &[&'a Package], path: &Path) -> Option<(&'a Package, &'a Target)> {
+    packages
+        .iter()
+        .flat_map(|package| package.targets.iter().map(move |target| (*package, target)))
+        .filter_map(|(package, target)| {
+            let package_dir = package.manifest_path.parent()?.as_std_path();
+            let target_dir = target.src_path.parent()?.as_std_path();
+            if !path.starts_with(package_dir) {
+                return None;
+            }
+            let score = (
+                package_dir.components().count(),
+                path.starts_with(target_dir),
+                target_dir.components().count(),
+            );
+            Some((score, (package, target)))
+        })
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, result)| result)
+}
+
+fn create_span(
+    location: &Location,
+    label: Option<String>,
+    cache: &mut SourceCache,
+) -> DiagnosticSpan {
+    let column = location.column.max(1);
+    let (byte_start, text) = match cache.line(&location.file, location.line) {
+        Some((offset, line)) => {
+            let column_offset = line
+                .char_indices()
+                .nth(column as usize - 1)
+                .map(|(index, _)| index)
+                .unwrap_or(line.len());
+            (
+                offset + column_offset,
+                vec![DiagnosticSpanLine {
+                    text: line.to_string(),
+                    highlight_start: column,
+                    highlight_end: column + 1,
+                }],
+            )
+        }
+        None => (0, vec![]),
+    };
+    DiagnosticSpan {
+        file_name: location.file.clone(),
+        byte_start,
+        byte_end: byte_start + 1,
+        line_start: location.line,
+        line_end: location.line,
+        column_start: column,
+        column_end: column + 1,
+        is_primary: true,
+        text,
+        label,
+        suggested_replacement: None,
+        suggestion_applicability: None,
+        expansion: None,
+    }
+}
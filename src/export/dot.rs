@@ -0,0 +1,13 @@
+//! Export of the inlining graph in the Graphviz DOT format.
+use std::io::Write;
+
+use crate::inlining::InliningGraph;
+use crate::remark::Remark;
+
+/// Writes a graph of inlining decisions between callers and callees of the remarks.
+/// Remarks that do not describe a call are ignored.
+pub fn write_dot<W: Write>(remarks: &[Remark], mut writer: W) -> anyhow::Result<()> {
+    InliningGraph::from_remarks(remarks).write_dot(&mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,295 @@
+//! Export of remarks into JSON and JSON Lines.
+//!
+//! The exported structure is decoupled from the internal [`Remark`] model, so that it can stay
+//! stable. Whenever it changes in an incompatible way, [`JSON_SCHEMA_VERSION`] is incremented.
+use std::io::Write;
+
+use crate::diff::{ChangedRemark, RemarkDiff};
+use crate::metrics::FunctionMetrics;
+use crate::remark::{Function, Location, MessagePart, Remark, RemarkCategory};
+
+/// Version of the exported JSON schema.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(serde::Serialize)]
+struct JsonDocument<'a> {
+    schema_version: u32,
+    remarks: Vec<JsonRemark<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonLine<'a> {
+    schema_version: u32,
+    #[serde(flatten)]
+    remark: JsonRemark<'a>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonRemark<'a> {
+    pass: &'a str,
+    name: &'a str,
+    function: JsonFunction<'a>,
+    message: Vec<JsonMessagePart<'a>>,
+    hotness: Option<i32>,
+    /// Compilation stage that has generated the remark.
+    stage: &'static str,
+    /// Type of the remark (`missed`, `passed` or `analysis`).
+    #[serde(rename = "type")]
+    remark_type: &'static str,
+    /// Panic path category of the remark (`bounds-check` or `panic-path`).
+    category: Option<RemarkCategory>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonFunction<'a> {
+    name: &'a str,
+    mangled_name: Option<&'a str>,
+    location: Option<&'a Location>,
+}
+
+impl<'a> From<&'a Function> for JsonFunction<'a> {
+    fn from(function: &'a Function) -> Self {
+        Self {
+            name: &function.name,
+            mangled_name: function.mangled_name.as_deref(),
+            location: function.location.as_ref(),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct JsonDiffDocument<'a> {
+    schema_version: u32,
+    new: Vec<JsonRemark<'a>>,
+    fixed: Vec<JsonRemark<'a>>,
+    changed: Vec<JsonChangedRemark<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonChangedRemark<'a> {
+    old: JsonRemark<'a>,
+    new: JsonRemark<'a>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonMetricsDocument<'a> {
+    schema_version: u32,
+    functions: Vec<JsonFunctionMetrics<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonFunctionMetrics<'a> {
+    function: JsonFunction<'a>,
+    /// Size of the stack frame in bytes.
+    stack_size: Option<u64>,
+    instruction_count: Option<u64>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+enum JsonMessagePart<'a> {
+    Text {
+        text: &'a str,
+    },
+    Annotated {
+        text: &'a str,
+        location: &'a Location,
+    },
+}
+
+impl<'a> From<&'a Remark> for JsonRemark<'a> {
+    fn from(remark: &'a Remark) -> Self {
+        Self {
+            pass: &remark.pass,
+            name: &remark.name,
+            function: (&remark.function).into(),
+            message: remark
+                .message
+                .iter()
+                .map(|part| match part {
+                    MessagePart::String(text) => JsonMessagePart::Text { text },
+                    MessagePart::AnnotatedString { message, location } => {
+                        JsonMessagePart::Annotated {
+                            text: message,
+                            location,
+                        }
+                    }
+                })
+                .collect(),
+            hotness: remark.hotness,
+            stage: remark.stage.as_str(),
+            remark_type: remark.remark_type.as_str(),
+            category: remark.category,
+        }
+    }
+}
+
+/// Writes the remarks as a single JSON document.
+pub fn write_json<W: Write>(remarks: &[Remark], mut writer: W) -> anyhow::Result<()> {
+    let document = JsonDocument {
+        schema_version: JSON_SCHEMA_VERSION,
+        remarks: remarks.iter().map(JsonRemark::from).collect(),
+    };
+    serde_json::to_writer_pretty(&mut writer, &document)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the remarks as JSON Lines, with a single remark per line.
+/// Each line contains the schema version, so that the lines can be processed independently.
+pub fn write_json_lines<W: Write>(remarks: &[Remark], mut writer: W) -> anyhow::Result<()> {
+    for remark in remarks {
+        let line = JsonLine {
+            schema_version: JSON_SCHEMA_VERSION,
+            remark: remark.into(),
+        };
+        serde_json::to_writer(&mut writer, &line)?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the differences between remarks of two builds (see [`crate::diff`]) as a single JSON
+/// document.
+pub fn write_diff_json<W: Write>(diff: &RemarkDiff, mut writer: W) -> anyhow::Result<()> {
+    let document = JsonDiffDocument {
+        schema_version: JSON_SCHEMA_VERSION,
+        new: diff.new.iter().map(JsonRemark::from).collect(),
+        fixed: diff.fixed.iter().map(JsonRemark::from).collect(),
+        changed: diff
+            .changed
+            .iter()
+            .map(|ChangedRemark { old, new }| JsonChangedRemark {
+                old: old.into(),
+                new: new.into(),
+            })
+            .collect(),
+    };
+    serde_json::to_writer_pretty(&mut writer, &document)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes metrics of functions (see [`crate::metrics`]) as a single JSON document.
+pub fn write_metrics_json<W: Write>(
+    metrics: &[FunctionMetrics],
+    mut writer: W,
+) -> anyhow::Result<()> {
+    let document = JsonMetricsDocument {
+        schema_version: JSON_SCHEMA_VERSION,
+        functions: metrics
+            .iter()
+            .map(|metrics| JsonFunctionMetrics {
+                function: (&metrics.function).into(),
+                stack_size: metrics.stack_size,
+                instruction_count: metrics.instruction_count,
+            })
+            .collect(),
+    };
+    serde_json::to_writer_pretty(&mut writer, &document)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::{ChangedRemark, RemarkDiff};
+    use crate::export::json::{write_diff_json, write_json, write_json_lines};
+    use crate::remark::fixtures;
+    use crate::remark::Remark;
+
+    #[test]
+    fn json_document() {
+        let mut output = vec![];
+        write_json(&[remark()], &mut output).unwrap();
+        insta::assert_snapshot!(String::from_utf8(output).unwrap(), @r###"
+        {
+          "schema_version": 1,
+          "remarks": [
+            {
+              "pass": "inline",
+              "name": "NoDefinition",
+              "function": {
+                "name": "foo::main",
+                "mangled_name": "_ZN3foo4main17hc92ae132ef1efa8eE",
+                "location": {
+                  "file": "src/main.rs",
+                  "line": 6,
+                  "column": 5
+                }
+              },
+              "message": [
+                {
+                  "kind": "annotated",
+                  "text": "foo::bar",
+                  "location": {
+                    "file": "src/lib.rs",
+                    "line": 1,
+                    "column": 0
+                  }
+                },
+                {
+                  "kind": "text",
+                  "text": " will not be inlined"
+                }
+              ],
+              "hotness": 10,
+              "stage": "pre-link",
+              "type": "missed",
+              "category": null
+            }
+          ]
+        }
+        "###);
+    }
+
+    #[test]
+    fn json_lines() {
+        let mut output = vec![];
+        write_json_lines(&[remark(), remark()], &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(value["schema_version"], 1);
+            assert_eq!(value["name"], "NoDefinition");
+        }
+    }
+
+    #[test]
+    fn json_diff() {
+        let diff = RemarkDiff {
+            new: vec![remark()],
+            fixed: vec![],
+            changed: vec![ChangedRemark {
+                old: remark(),
+                new: remark().with_hotness(20),
+            }],
+        };
+        let mut output = vec![];
+        write_diff_json(&diff, &mut output).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(value["schema_version"], 1);
+        assert_eq!(value["new"][0]["name"], "NoDefinition");
+        assert_eq!(value["fixed"].as_array().unwrap().len(), 0);
+        assert_eq!(value["changed"][0]["old"]["hotness"], 10);
+        assert_eq!(value["changed"][0]["new"]["hotness"], 20);
+    }
+
+    fn remark() -> Remark {
+        let mut remark = fixtures::remark("inline", "NoDefinition")
+            .at("src/main.rs", 6)
+            .with_message(vec![
+                fixtures::annotated("foo::bar", fixtures::location("src/lib.rs", 1, 0)),
+                fixtures::text(" will not be inlined"),
+            ])
+            .with_hotness(10);
+        remark.function.mangled_name = Some("_ZN3foo4main17hc92ae132ef1efa8eE".to_string());
+        remark
+    }
+}
@@ -0,0 +1,173 @@
+//! Export of remarks into formats that can be consumed by other tools.
+use std::borrow::Cow;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::knowledge::KnowledgeBase;
+use crate::metrics::compute_metrics;
+use crate::remark::{Remark, RemarkType};
+
+pub mod diagnostic;
+pub mod dot;
+pub mod json;
+pub mod sarif;
+pub mod text;
+
+/// Format in which are the loaded remarks outputted.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Render a website with the remarks.
+    Html,
+    /// Print the remarks in a format similar to `rustc` diagnostics.
+    Text,
+    /// JSON document with all remarks.
+    Json,
+    /// JSON Lines, with a single remark per line.
+    Jsonl,
+    /// SARIF log, which can be consumed by code-scanning tools.
+    Sarif,
+    /// Graphviz DOT graph of inlining decisions between callers and callees.
+    Dot,
+    /// JSON document with the stack size and instruction count of each function.
+    /// Requires analysis remarks.
+    MetricsJson,
+}
+
+/// Order of exported remarks.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemarkSort {
+    /// Sort by source file and line.
+    File,
+    /// Sort by hotness in descending order, remarks without hotness come last.
+    Hotness,
+}
+
+pub fn sort_remarks(remarks: &mut [Remark], sort: RemarkSort) {
+    let location = |remark: &Remark| {
+        remark
+            .function
+            .location
+            .as_ref()
+            .map(|l| (l.file.clone(), l.line, l.column))
+    };
+    match sort {
+        RemarkSort::File => remarks.sort_by_key(location),
+        RemarkSort::Hotness => remarks.sort_by(|a, b| {
+            b.hotness
+                .cmp(&a.hotness)
+                .then_with(|| location(a).cmp(&location(b)))
+        }),
+    }
+}
+
+/// Writes the remarks in the given machine-readable format.
+/// Returns an error if `format` is [`OutputFormat::Html`], which has to be rendered separately
+/// with [`crate::render::render_remarks`].
+///
+/// Passed and analysis remarks are only written by formats that distinguish remark types (JSON and
+/// JSON Lines) or that need them (function metrics). Other formats only contain missed remarks.
+pub fn export_remarks<W: Write>(
+    remarks: &[Remark],
+    format: OutputFormat,
+    source_dir: &Path,
+    knowledge: &KnowledgeBase,
+    writer: W,
+) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Html => Err(anyhow::anyhow!(
+            "HTML output cannot be exported, it has to be rendered into a directory"
+        )),
+        OutputFormat::Text => {
+            text::write_text(&missed_remarks(remarks), source_dir, knowledge, writer)
+        }
+        OutputFormat::Json => json::write_json(remarks, writer),
+        OutputFormat::Jsonl => json::write_json_lines(remarks, writer),
+        OutputFormat::Sarif => sarif::write_sarif(&missed_remarks(remarks), source_dir, writer),
+        OutputFormat::Dot => dot::write_dot(&missed_remarks(remarks), writer),
+        OutputFormat::MetricsJson => json::write_metrics_json(&compute_metrics(remarks), writer),
+    }
+}
+
+fn missed_remarks(remarks: &[Remark]) -> Cow<'_, [Remark]> {
+    if remarks
+        .iter()
+        .all(|remark| remark.remark_type == RemarkType::Missed)
+    {
+        Cow::Borrowed(remarks)
+    } else {
+        Cow::Owned(
+            remarks
+                .iter()
+                .filter(|remark| remark.remark_type == RemarkType::Missed)
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+/// Opens the destination for exported remarks.
+/// If no path is given, the remarks are written to stdout.
+pub fn open_output(path: Option<&Path>) -> anyhow::Result<Box<dyn Write>> {
+    match path {
+        Some(path) => {
+            let file = std::fs::File::create(path)
+                .with_context(|| format!("Cannot create output file {}", path.display()))?;
+            Ok(Box::new(BufWriter::new(file)))
+        }
+        None => Ok(Box::new(BufWriter::new(std::io::stdout().lock()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::export::{export_remarks, OutputFormat};
+    use crate::knowledge::KnowledgeBase;
+    use crate::remark::fixtures;
+    use crate::remark::RemarkType;
+
+    #[test]
+    fn export_html_error() {
+        let mut output = vec![];
+        assert!(export_remarks(
+            &[],
+            OutputFormat::Html,
+            Path::new("."),
+            &KnowledgeBase::default(),
+            &mut output
+        )
+        .is_err());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn export_missed_only() {
+        let remarks = vec![
+            fixtures::remark("inline", "NoDefinition").calling("foo::missed"),
+            fixtures::remark("inline", "Inlined")
+                .calling("foo::passed")
+                .with_type(RemarkType::Passed),
+        ];
+        let export = |format| {
+            let mut output = vec![];
+            export_remarks(
+                &remarks,
+                format,
+                Path::new("."),
+                &KnowledgeBase::default(),
+                &mut output,
+            )
+            .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        for format in [OutputFormat::Text, OutputFormat::Sarif, OutputFormat::Dot] {
+            let output = export(format);
+            assert!(output.contains("NoDefinition") || output.contains("foo::missed"));
+            assert!(!output.contains("Inlined") && !output.contains("foo::passed"));
+        }
+        assert_eq!(export(OutputFormat::Jsonl).lines().count(), 2);
+    }
+}
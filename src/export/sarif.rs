@@ -0,0 +1,435 @@
+//! Export of remarks into the [SARIF](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+//! format, which is understood by many code-scanning and code review tools.
+use std::io::Write;
+use std::path::Path;
+
+use crate::remark::{Location, MessagePart, Remark};
+use crate::utils::data_structures::Map;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+/// Base URI identifier of paths relative to the source directory.
+const SOURCE_ROOT_ID: &str = "%SRCROOT%";
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run {
+    tool: Tool,
+    original_uri_base_ids: Map<&'static str, ArtifactLocation>,
+    results: Vec<SarifResult>,
+}
+
+#[derive(serde::Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: String,
+    name: String,
+    short_description: Message,
+    properties: RuleProperties,
+}
+
+#[derive(serde::Serialize)]
+struct RuleProperties {
+    pass: String,
+}
+
+#[derive(serde::Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<SarifLocation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<SarifLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rank: Option<f64>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    physical_location: Option<PhysicalLocation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    logical_locations: Vec<LogicalLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    /// Lines in SARIF start at 1, so the region is omitted for remarks without a line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_column: Option<u32>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogicalLocation {
+    fully_qualified_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decorated_name: Option<String>,
+    kind: &'static str,
+}
+
+/// Writes the remarks as a SARIF log with a single run.
+///
+/// Each remark kind (a pass and a remark name) becomes a rule with the `<pass>/<name>` ID and
+/// each remark becomes a result. The location of the remark
+/// is the primary location of the result, and annotated parts of the message (e.g. callees)
+/// become related locations, which are linked from the message text.
+/// Hotness is normalized into the `0-100` rank range.
+pub fn write_sarif<W: Write>(
+    remarks: &[Remark],
+    source_dir: &Path,
+    mut writer: W,
+) -> anyhow::Result<()> {
+    let mut rules: Vec<Rule> = vec![];
+    let mut rule_indices: Map<(&str, &str), usize> = Map::default();
+    let max_hotness = remarks
+        .iter()
+        .filter_map(|remark| remark.hotness)
+        .max()
+        .unwrap_or(0);
+
+    let results = remarks
+        .iter()
+        .map(|remark| {
+            let key = (remark.pass.as_str(), remark.name.as_str());
+            let rule_index = *rule_indices.entry(key).or_insert_with(|| {
+                rules.push(Rule {
+                    id: rule_id(remark),
+                    name: remark.name.clone(),
+                    short_description: Message {
+                        text: format!(
+                            "LLVM optimization remark `{}` emitted by the `{}` pass",
+                            remark.name, remark.pass
+                        ),
+                    },
+                    properties: RuleProperties {
+                        pass: remark.pass.clone(),
+                    },
+                });
+                rules.len() - 1
+            });
+            create_result(remark, rule_index, max_hotness)
+        })
+        .collect();
+
+    let mut base_ids = Map::default();
+    base_ids.insert(
+        SOURCE_ROOT_ID,
+        ArtifactLocation {
+            uri: directory_uri(source_dir),
+            uri_base_id: None,
+        },
+    );
+
+    let log = SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: "cargo-remark",
+                    version: env!("CARGO_PKG_VERSION"),
+                    information_uri: env!("CARGO_PKG_REPOSITORY"),
+                    rules,
+                },
+            },
+            original_uri_base_ids: base_ids,
+            results,
+        }],
+    };
+    serde_json::to_writer_pretty(&mut writer, &log)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn create_result(remark: &Remark, rule_index: usize, max_hotness: i32) -> SarifResult {
+    let mut text = String::new();
+    let mut related_locations = vec![];
+    for part in &remark.message {
+        match part {
+            MessagePart::String(message) => text.push_str(&escape_message(message)),
+            MessagePart::AnnotatedString { message, location } => {
+                let id = related_locations.len() + 1;
+                text.push_str(&format!("[{}]({id})", escape_message(message)));
+                related_locations.push(SarifLocation {
+                    id: Some(id),
+                    physical_location: Some(physical_location(location)),
+                    logical_locations: vec![],
+                    message: Some(Message {
+                        text: message.clone(),
+                    }),
+                });
+            }
+        }
+    }
+    if text.is_empty() {
+        text = remark.message_text();
+    }
+
+    let rank = remark.hotness.map(|hotness| {
+        if max_hotness > 0 {
+            (hotness.max(0) as f64 / max_hotness as f64) * 100.0
+        } else {
+            0.0
+        }
+    });
+
+    SarifResult {
+        rule_id: rule_id(remark),
+        rule_index,
+        level: "note",
+        message: Message { text },
+        locations: vec![SarifLocation {
+            id: None,
+            physical_location: remark.function.location.as_ref().map(physical_location),
+            logical_locations: vec![LogicalLocation {
+                fully_qualified_name: remark.function.name.clone(),
+                decorated_name: remark.function.mangled_name.clone(),
+                kind: "function",
+            }],
+            message: None,
+        }],
+        related_locations,
+        rank,
+    }
+}
+
+fn physical_location(location: &Location) -> PhysicalLocation {
+    let path = Path::new(&location.file);
+    let artifact_location = if path.is_absolute() {
+        ArtifactLocation {
+            uri: file_uri(&location.file),
+            uri_base_id: None,
+        }
+    } else {
+        ArtifactLocation {
+            uri: encode_uri_path(&location.file.replace('\\', "/")),
+            uri_base_id: Some(SOURCE_ROOT_ID),
+        }
+    };
+    PhysicalLocation {
+        artifact_location,
+        region: (location.line > 0).then(|| Region {
+            start_line: location.line,
+            start_column: (location.column > 0).then_some(location.column),
+        }),
+    }
+}
+
+fn rule_id(remark: &Remark) -> String {
+    format!("{}/{}", remark.pass, remark.name)
+}
+
+fn directory_uri(path: &Path) -> String {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut uri = file_uri(&path.to_string_lossy());
+    if !uri.ends_with('/') {
+        uri.push('/');
+    }
+    uri
+}
+
+fn file_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", encode_uri_path(&path))
+    } else {
+        // Windows paths (e.g. `C:/foo`)
+        format!("file:///{}", encode_uri_path(&path))
+    }
+}
+
+fn encode_uri_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Square brackets have to be escaped in SARIF messages, because they are used for embedded
+/// links to related locations.
+fn escape_message(message: &str) -> String {
+    message
+        .replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::export::sarif::write_sarif;
+    use crate::remark::fixtures::{self, remark};
+
+    #[test]
+    fn sarif_result() {
+        let remarks = vec![
+            remark("inline", "NoDefinition")
+                .at("src/main.rs", 6)
+                .with_message(vec![
+                    fixtures::annotated(
+                        "<[T]>::len",
+                        fixtures::location("/rust/src/slice.rs", 10, 0),
+                    ),
+                    fixtures::text(" will not be inlined"),
+                ])
+                .with_hotness(50),
+            remark("inline", "NoDefinition")
+                .in_function("foo::bar")
+                .unlocated()
+                .with_text("foo")
+                .with_hotness(100),
+        ];
+        let mut output = vec![];
+        write_sarif(&remarks, Path::new("/nonexistent/project"), &mut output).unwrap();
+        let log: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let run = &log["runs"][0];
+
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            run["originalUriBaseIds"]["%SRCROOT%"]["uri"],
+            "file:///nonexistent/project/"
+        );
+        insta::assert_snapshot!(serde_json::to_string_pretty(&run["results"][0]).unwrap(), @r###"
+        {
+          "level": "note",
+          "locations": [
+            {
+              "logicalLocations": [
+                {
+                  "fullyQualifiedName": "foo::main",
+                  "kind": "function"
+                }
+              ],
+              "physicalLocation": {
+                "artifactLocation": {
+                  "uri": "src/main.rs",
+                  "uriBaseId": "%SRCROOT%"
+                },
+                "region": {
+                  "startColumn": 5,
+                  "startLine": 6
+                }
+              }
+            }
+          ],
+          "message": {
+            "text": "[<\\[T\\]>::len](1) will not be inlined"
+          },
+          "rank": 50.0,
+          "relatedLocations": [
+            {
+              "id": 1,
+              "message": {
+                "text": "<[T]>::len"
+              },
+              "physicalLocation": {
+                "artifactLocation": {
+                  "uri": "file:///rust/src/slice.rs"
+                },
+                "region": {
+                  "startLine": 10
+                }
+              }
+            }
+          ],
+          "ruleId": "inline/NoDefinition",
+          "ruleIndex": 0
+        }
+        "###);
+        assert_eq!(run["results"][1]["rank"], 100.0);
+    }
+
+    #[test]
+    fn sarif_rules_per_pass() {
+        let remark = |pass: &str, line: u32| {
+            remark(pass, "Missed")
+                .at("src/main.rs", line)
+                .with_text("foo")
+        };
+        let remarks = vec![remark("licm", 0), remark("gvn", 3), remark("licm", 5)];
+        let mut output = vec![];
+        write_sarif(&remarks, Path::new("/nonexistent/project"), &mut output).unwrap();
+        let log: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let run = &log["runs"][0];
+
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0]["id"], "licm/Missed");
+        assert_eq!(rules[0]["properties"]["pass"], "licm");
+        assert_eq!(rules[1]["id"], "gvn/Missed");
+        assert_eq!(rules[1]["properties"]["pass"], "gvn");
+        assert_eq!(run["results"][2]["ruleIndex"], 0);
+
+        // Line 0 is not a valid SARIF line
+        let location = &run["results"][0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/main.rs");
+        assert!(location.get("region").is_none());
+        assert_eq!(
+            run["results"][1]["locations"][0]["physicalLocation"]["region"]["startLine"],
+            3
+        );
+    }
+}
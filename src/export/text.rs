@@ -0,0 +1,215 @@
+//! Human-readable output of remarks, formatted similarly to `rustc` diagnostics.
+use std::io::Write;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::knowledge::KnowledgeBase;
+use crate::remark::{Location, MessagePart, Remark};
+use crate::render::resolve_path;
+use crate::utils::data_structures::Map;
+
+/// Caches lines of source files, so that each file is read only once.
+pub struct SourceCache<'a> {
+    source_dir: &'a Path,
+    /// Byte offset of the start of each line and the line itself.
+    files: Map<String, Option<Vec<(usize, String)>>>,
+}
+
+impl<'a> SourceCache<'a> {
+    pub fn new(source_dir: &'a Path) -> Self {
+        Self {
+            source_dir,
+            files: Map::default(),
+        }
+    }
+
+    /// Returns the byte offset of the start of the given (1-based) line, and the line itself.
+    pub fn line(&mut self, file: &str, line: u32) -> Option<(usize, &str)> {
+        let source_dir = self.source_dir;
+        let lines = self.files.entry(file.to_string()).or_insert_with(|| {
+            let path = resolve_path(source_dir, Path::new(file));
+            let content = std::fs::read_to_string(path).ok()?;
+            let mut offset = 0;
+            Some(
+                content
+                    .split_inclusive('\n')
+                    .map(|line| {
+                        let start = offset;
+                        offset += line.len();
+                        (start, line.trim_end_matches(['\n', '\r']).to_string())
+                    })
+                    .collect(),
+            )
+        });
+        lines
+            .as_ref()?
+            .get((line as usize).checked_sub(1)?)
+            .map(|(offset, line)| (*offset, line.as_str()))
+    }
+}
+
+/// Writes the remarks in a format similar to `rustc` diagnostics, with a source snippet
+/// pointing to the location of each remark and an explanation from the knowledge base.
+pub fn write_text<W: Write>(
+    remarks: &[Remark],
+    source_dir: &Path,
+    knowledge: &KnowledgeBase,
+    mut writer: W,
+) -> anyhow::Result<()> {
+    let mut cache = SourceCache::new(source_dir);
+    for remark in remarks {
+        write_remark_snippet(&mut writer, remark, &mut cache, Some(knowledge))?;
+        writeln!(writer)?;
+    }
+    writeln!(writer, "{} remark(s)", remarks.len().to_string().bold())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes a single remark with its source snippet. The explanation is only included if a
+/// knowledge base is passed.
+pub(crate) fn write_remark_snippet<W: Write>(
+    writer: &mut W,
+    remark: &Remark,
+    cache: &mut SourceCache,
+    knowledge: Option<&KnowledgeBase>,
+) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "{}{}{} {}",
+        "remark[".yellow().bold(),
+        remark.name.yellow().bold(),
+        "]:".yellow().bold(),
+        format!("{} in `{}`", remark.pass, remark.function.name).bold()
+    )?;
+
+    let gutter_width = remark
+        .function
+        .location
+        .as_ref()
+        .map(|location| location.line.to_string().len())
+        .unwrap_or(1);
+    let gutter = " ".repeat(gutter_width);
+    let separator = "|".blue().bold();
+
+    if let Some(location) = &remark.function.location {
+        writeln!(
+            writer,
+            "{gutter}{} {}",
+            "-->".blue().bold(),
+            format_location(location)
+        )?;
+        if let Some((_, line)) = cache.line(&location.file, location.line) {
+            // Reuse the whitespace of the source line, so that the caret is aligned even if the
+            // line contains tabs.
+            let offset: String = line
+                .chars()
+                .take((location.column as usize).saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            writeln!(writer, "{gutter} {separator}")?;
+            writeln!(
+                writer,
+                "{} {separator} {line}",
+                location.line.to_string().blue().bold()
+            )?;
+            writeln!(
+                writer,
+                "{gutter} {separator} {offset}{}",
+                "^".yellow().bold()
+            )?;
+        }
+        writeln!(writer, "{gutter} {separator}")?;
+    }
+
+    let equals = "=".blue().bold();
+    writeln!(writer, "{gutter} {equals} {}", remark.message_text())?;
+    for part in &remark.message {
+        if let MessagePart::AnnotatedString { message, location } = part {
+            writeln!(
+                writer,
+                "{gutter} {equals} {}: `{message}` is defined at {}",
+                "note".bold(),
+                format_location(location)
+            )?;
+        }
+    }
+    if let Some(hotness) = remark.hotness {
+        writeln!(writer, "{gutter} {equals} {}: {hotness}", "hotness".bold())?;
+    }
+    if let Some(entry) = knowledge.and_then(|knowledge| knowledge.find(remark)) {
+        writeln!(
+            writer,
+            "{gutter} {equals} {}: {}",
+            "note".bold(),
+            entry.explanation
+        )?;
+        for suggestion in &entry.suggestions {
+            writeln!(writer, "{gutter} {equals} {}: {suggestion}", "help".bold())?;
+        }
+    }
+    Ok(())
+}
+
+fn format_location(location: &Location) -> String {
+    if location.column > 0 {
+        format!("{}:{}:{}", location.file, location.line, location.column)
+    } else {
+        format!("{}:{}", location.file, location.line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::text::write_text;
+    use crate::knowledge::KnowledgeBase;
+    use crate::remark::fixtures;
+
+    #[test]
+    fn text_snippet() {
+        colored::control::set_override(false);
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/main.rs"),
+            "fn main() {\n    foo();\n}\n",
+        )
+        .unwrap();
+
+        let remark = fixtures::remark("inline", "NoDefinition")
+            .at("src/main.rs", 2)
+            .with_message(vec![
+                fixtures::annotated("foo::foo", fixtures::location("src/lib.rs", 10, 0)),
+                fixtures::text(" will not be inlined into foo::main"),
+            ])
+            .with_hotness(10);
+
+        let mut output = vec![];
+        write_text(
+            &[remark],
+            dir.path(),
+            &KnowledgeBase::default(),
+            &mut output,
+        )
+        .unwrap();
+        insta::assert_snapshot!(String::from_utf8(output).unwrap(), @r###"
+        remark[NoDefinition]: inline in `foo::main`
+         --> src/main.rs:2:5
+          |
+        2 |     foo();
+          |     ^
+          |
+          = foo::foo will not be inlined into foo::main
+          = note: `foo::foo` is defined at src/lib.rs:10
+          = hotness: 10
+          = note: The function could not be inlined, because its body is not available in the current codegen unit. This happens for non-generic functions from other crates that are not marked with `#[inline]`, for functions from other codegen units of the same crate and for foreign functions.
+          = help: If the callee is small and hot, mark it with `#[inline]`, so that its body is available to other crates and codegen units.
+          = help: Enable link-time optimization (`lto = "thin"` or `lto = "fat"` in the Cargo profile), which allows inlining across crates.
+          = help: Use `codegen-units = 1` in the Cargo profile, so that all functions of a crate are in the same codegen unit.
+
+        1 remark(s)
+        "###);
+    }
+}
@@ -0,0 +1,244 @@
+//! Graph of inlining decisions, built from the caller/callee pairs of inlining remarks.
+use std::io::Write;
+
+use crate::remark::{CallEdge, Location, MessagePart, Remark};
+use crate::utils::data_structures::Map;
+
+/// Calls whose cost exceeds the threshold by at most this fraction of the threshold are
+/// considered to be near misses.
+pub const DEFAULT_NEAR_MISS_MARGIN: f64 = 0.5;
+
+/// Calls from one function to another with the same inlining decision.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct InliningEdge {
+    /// Index of the caller in [`InliningGraph::functions`].
+    pub caller: usize,
+    /// Index of the callee in [`InliningGraph::functions`].
+    pub callee: usize,
+    /// Name of the remark that describes the decision (e.g. `TooCostly`).
+    pub decision: String,
+    pub cost: Option<String>,
+    pub threshold: Option<String>,
+    /// Number of calls with this decision.
+    pub count: usize,
+    /// Maximum hotness of the calls.
+    pub hotness: Option<i32>,
+}
+
+impl InliningEdge {
+    /// Returns a label that describes the decision, e.g. `TooCostly (cost=345, threshold=250)`.
+    pub fn label(&self) -> String {
+        let mut label = self.decision.clone();
+        match (&self.cost, &self.threshold) {
+            (Some(cost), Some(threshold)) => {
+                label.push_str(&format!(" (cost={cost}, threshold={threshold})"))
+            }
+            (Some(cost), None) => label.push_str(&format!(" (cost={cost})")),
+            (None, Some(threshold)) => label.push_str(&format!(" (threshold={threshold})")),
+            (None, None) => {}
+        }
+        if self.count > 1 {
+            label.push_str(&format!(", {} calls", self.count));
+        }
+        label
+    }
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+pub struct InliningGraph {
+    /// Demangled names of functions, sorted alphabetically.
+    pub functions: Vec<String>,
+    pub edges: Vec<InliningEdge>,
+}
+
+impl InliningGraph {
+    /// Creates a graph from remarks that describe a call (see [`Remark::call`]).
+    /// Other remarks are ignored.
+    pub fn from_remarks<'a, I: IntoIterator<Item = &'a Remark>>(remarks: I) -> Self {
+        type EdgeKey<'a> = (&'a str, &'a str, &'a str, Option<&'a str>, Option<&'a str>);
+        let mut edges: Map<EdgeKey, (usize, Option<i32>)> = Map::default();
+        for remark in remarks {
+            let Some(call) = &remark.call else {
+                continue;
+            };
+            let key = (
+                call.caller.as_str(),
+                call.callee.as_str(),
+                remark.name.as_str(),
+                call.cost.as_deref(),
+                call.threshold.as_deref(),
+            );
+            let (count, hotness) = edges.entry(key).or_default();
+            *count += 1;
+            *hotness = (*hotness).max(remark.hotness);
+        }
+
+        let mut functions: Vec<&str> = edges
+            .keys()
+            .flat_map(|(caller, callee, ..)| [*caller, *callee])
+            .collect();
+        functions.sort_unstable();
+        functions.dedup();
+        let index = |function: &str| functions.binary_search(&function).unwrap();
+
+        let mut edges: Vec<InliningEdge> = edges
+            .into_iter()
+            .map(
+                |((caller, callee, decision, cost, threshold), (count, hotness))| InliningEdge {
+                    caller: index(caller),
+                    callee: index(callee),
+                    decision: decision.to_string(),
+                    cost: cost.map(|cost| cost.to_string()),
+                    threshold: threshold.map(|threshold| threshold.to_string()),
+                    count,
+                    hotness,
+                },
+            )
+            .collect();
+        edges.sort_by(|a, b| {
+            (a.caller, a.callee, &a.decision, &a.cost, &a.threshold).cmp(&(
+                b.caller,
+                b.callee,
+                &b.decision,
+                &b.cost,
+                &b.threshold,
+            ))
+        });
+
+        Self {
+            functions: functions.into_iter().map(|f| f.to_string()).collect(),
+            edges,
+        }
+    }
+
+    /// Writes the graph in the Graphviz DOT format.
+    pub fn write_dot<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writeln!(writer, "digraph inlining {{")?;
+        writeln!(writer, "    node [shape=box, fontname=\"monospace\"];")?;
+        for (index, function) in self.functions.iter().enumerate() {
+            writeln!(writer, "    n{index} [label=\"{}\"];", escape_dot(function))?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                writer,
+                "    n{} -> n{} [label=\"{}\"];",
+                edge.caller,
+                edge.callee,
+                escape_dot(&edge.label())
+            )?;
+        }
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+}
+
+/// Call that was not inlined, although its cost was only slightly above the inlining threshold.
+#[derive(Debug)]
+pub struct NearMiss<'a> {
+    pub remark: &'a Remark,
+    pub call: &'a CallEdge,
+    pub cost: i64,
+    pub threshold: i64,
+}
+
+impl<'a> NearMiss<'a> {
+    /// How much was the threshold exceeded, relative to the threshold.
+    pub fn margin(&self) -> f64 {
+        (self.cost - self.threshold) as f64 / self.threshold.abs().max(1) as f64
+    }
+
+    /// Location of the definition of the callee, if the remark contains it.
+    pub fn callee_location(&self) -> Option<&'a Location> {
+        self.remark.message.iter().find_map(|part| match part {
+            MessagePart::AnnotatedString { message, location } if message == &self.call.callee => {
+                Some(location)
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Finds calls that were not inlined because their cost exceeded the threshold by at most
+/// `max_margin` (relative to the threshold).
+/// The calls are sorted by their margin and then by hotness, so that calls that came closest to
+/// being inlined are first.
+pub fn find_near_misses(remarks: &[Remark], max_margin: f64) -> Vec<NearMiss<'_>> {
+    let mut near_misses: Vec<NearMiss> = remarks
+        .iter()
+        .filter_map(|remark| {
+            let call = remark.call.as_ref()?;
+            let cost = call.cost.as_deref()?.parse().ok()?;
+            let threshold = call.threshold.as_deref()?.parse().ok()?;
+            let near_miss = NearMiss {
+                remark,
+                call,
+                cost,
+                threshold,
+            };
+            (cost > threshold && near_miss.margin() <= max_margin).then_some(near_miss)
+        })
+        .collect();
+    near_misses.sort_by(|a, b| {
+        a.margin()
+            .total_cmp(&b.margin())
+            .then_with(|| b.remark.hotness.cmp(&a.remark.hotness))
+    });
+    near_misses
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inlining::{find_near_misses, InliningGraph, DEFAULT_NEAR_MISS_MARGIN};
+    use crate::remark::fixtures::remark;
+
+    #[test]
+    fn write_dot() {
+        let call = |name: &str, caller: &str, callee: &str| {
+            remark("inline", name).in_function(caller).calling(callee)
+        };
+        let remarks = vec![
+            call("TooCostly", "foo::main", "foo::bar").with_cost("345", "250"),
+            call("TooCostly", "foo::main", "foo::bar").with_cost("345", "250"),
+            call("NoDefinition", "foo::main", "<T as foo::Trait>::baz"),
+            call("NoDefinition", "foo::bar", "<T as foo::Trait>::baz"),
+        ];
+        let graph = InliningGraph::from_remarks(&remarks);
+        let mut output = vec![];
+        graph.write_dot(&mut output).unwrap();
+        insta::assert_snapshot!(String::from_utf8(output).unwrap(), @r###"
+        digraph inlining {
+            node [shape=box, fontname="monospace"];
+            n0 [label="<T as foo::Trait>::baz"];
+            n1 [label="foo::bar"];
+            n2 [label="foo::main"];
+            n1 -> n0 [label="NoDefinition"];
+            n2 -> n0 [label="NoDefinition"];
+            n2 -> n1 [label="TooCostly (cost=345, threshold=250), 2 calls"];
+        }
+        "###);
+    }
+
+    #[test]
+    fn near_misses() {
+        let call = |name: &str, caller: &str, callee: &str| {
+            remark("inline", name).in_function(caller).calling(callee)
+        };
+        let remarks = vec![
+            call("TooCostly", "foo::a", "foo::far").with_cost("500", "250"),
+            call("TooCostly", "foo::b", "foo::close").with_cost("260", "250"),
+            call("TooCostly", "foo::c", "foo::closer").with_cost("50", "45"),
+            call("TooCostly", "foo::d", "foo::never").with_cost("never", "250"),
+            call("Inlined", "foo::e", "foo::cheap").with_cost("100", "250"),
+            call("NoDefinition", "foo::f", "foo::extern"),
+        ];
+        let near_misses = find_near_misses(&remarks, DEFAULT_NEAR_MISS_MARGIN);
+        insta::assert_snapshot!(near_misses.iter().map(|near_miss| format!("{} -> {}: {:.2}", near_miss.call.caller, near_miss.call.callee, near_miss.margin())).collect::<Vec<_>>().join("\n"), @r###"
+        foo::b -> foo::close: 0.04
+        foo::c -> foo::closer: 0.11
+        "###);
+    }
+}
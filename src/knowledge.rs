@@ -0,0 +1,192 @@
+//! Knowledge base with plain-language explanations of remark kinds and suggestions how to resolve
+//! them. The built-in entries can be overridden and extended by a user file.
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::remark::Remark;
+
+/// Name of the knowledge base file that is loaded from the workspace root by default.
+pub const DEFAULT_KNOWLEDGE_FILE: &str = "remark-knowledge.toml";
+
+const BUILTIN_KNOWLEDGE: &str = include_str!("knowledge.toml");
+
+/// Explanation of a remark kind.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnowledgeEntry {
+    /// Pass that emits the remark (e.g. `inline`).
+    pub pass: String,
+    /// Remark name (e.g. `NoDefinition`). If it is missing, the entry is matched by its `message`,
+    /// or it matches all remarks of the pass.
+    pub name: Option<String>,
+    /// Text that has to be contained in the remark message. It is only used if the remark cannot
+    /// be matched by its name, i.e. for remarks parsed from text diagnostics, which do not have a
+    /// name, or if the entry does not have a name.
+    pub message: Option<String>,
+    /// What does the remark mean. Text in backticks is rendered as code.
+    pub explanation: String,
+    /// How can the remark be resolved. Text in backticks is rendered as code.
+    #[serde(default)]
+    pub suggestions: Vec<String>,
+}
+
+impl KnowledgeEntry {
+    pub fn matches(&self, remark: &Remark) -> bool {
+        if self.pass != remark.pass {
+            return false;
+        }
+        // Remarks parsed from text diagnostics use the pass as their name
+        let has_name = remark.name != remark.pass;
+        match (&self.name, &self.message) {
+            (Some(name), _) if has_name => name == &remark.name,
+            (_, Some(message)) => remark.message_text().contains(message.as_str()),
+            (None, None) => true,
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// File with knowledge base entries, in the following format:
+/// ```toml
+/// [[entry]]
+/// pass = "inline"
+/// name = "NoDefinition"
+/// explanation = "The body of the function is not available."
+/// suggestions = ["Mark the function with `#[inline]`."]
+/// ```
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnowledgeFile {
+    #[serde(default, rename = "entry")]
+    pub entries: Vec<KnowledgeEntry>,
+}
+
+impl KnowledgeFile {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read knowledge base file {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Cannot parse knowledge base file {}", path.display()))
+    }
+}
+
+/// Built-in entries, preceded by entries from a user file.
+#[derive(Debug)]
+pub struct KnowledgeBase {
+    entries: Vec<KnowledgeEntry>,
+}
+
+impl Default for KnowledgeBase {
+    fn default() -> Self {
+        Self::new(KnowledgeFile::default())
+    }
+}
+
+impl KnowledgeBase {
+    /// Entries of the user file take precedence over the built-in entries.
+    pub fn new(user_file: KnowledgeFile) -> Self {
+        let builtin: KnowledgeFile =
+            toml::from_str(BUILTIN_KNOWLEDGE).expect("Cannot parse built-in knowledge base");
+        let mut entries = user_file.entries;
+        entries.extend(builtin.entries);
+        Self { entries }
+    }
+
+    /// Loads the built-in entries and entries from `file`. If no file is given,
+    /// [`DEFAULT_KNOWLEDGE_FILE`] is loaded from `source_dir`, if it exists.
+    pub fn load(source_dir: &Path, file: Option<&Path>) -> anyhow::Result<Self> {
+        let user_file = match file {
+            Some(path) => KnowledgeFile::load(path)?,
+            None => {
+                let path = source_dir.join(DEFAULT_KNOWLEDGE_FILE);
+                if path.is_file() {
+                    KnowledgeFile::load(&path)?
+                } else {
+                    KnowledgeFile::default()
+                }
+            }
+        };
+        Ok(Self::new(user_file))
+    }
+
+    pub fn entries(&self) -> &[KnowledgeEntry] {
+        &self.entries
+    }
+
+    /// Returns the index of the first entry that matches the remark.
+    pub fn position(&self, remark: &Remark) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.matches(remark))
+    }
+
+    pub fn find(&self, remark: &Remark) -> Option<&KnowledgeEntry> {
+        self.position(remark).map(|index| &self.entries[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::knowledge::{KnowledgeBase, KnowledgeFile};
+    use crate::remark::fixtures::remark;
+
+    #[test]
+    fn builtin_entries() {
+        let knowledge = KnowledgeBase::default();
+        assert!(!knowledge.entries().is_empty());
+
+        let entry = knowledge
+            .find(&remark("inline", "TooCostly").with_text("foo not inlined"))
+            .unwrap();
+        assert_eq!(entry.name.as_deref(), Some("TooCostly"));
+        assert!(knowledge
+            .find(&remark("inline", "Unknown").with_text("foo not inlined"))
+            .is_none());
+    }
+
+    #[test]
+    fn match_text_remark_by_message() {
+        let knowledge = KnowledgeBase::default();
+        let entry = knowledge
+            .find(&remark("loop-vectorize", "loop-vectorize").with_text(
+                "loop not vectorized: cannot prove it is safe to reorder memory operations",
+            ))
+            .unwrap();
+        assert_eq!(entry.name.as_deref(), Some("CantReorderMemOps"));
+        // Entries without a message do not match remarks without a name
+        assert!(knowledge
+            .find(&remark("regalloc", "regalloc").with_text("3 reloads generated in loop"))
+            .is_none());
+    }
+
+    #[test]
+    fn user_entries_override_builtin() {
+        let user_file: KnowledgeFile = toml::from_str(
+            r#"
+[[entry]]
+pass = "inline"
+name = "NoDefinition"
+explanation = "Our own explanation"
+
+[[entry]]
+pass = "my-pass"
+explanation = "Custom pass"
+"#,
+        )
+        .unwrap();
+        let knowledge = KnowledgeBase::new(user_file);
+        assert_eq!(
+            knowledge
+                .find(&remark("inline", "NoDefinition").with_text("foo"))
+                .unwrap()
+                .explanation,
+            "Our own explanation"
+        );
+        assert_eq!(
+            knowledge
+                .find(&remark("my-pass", "Anything").with_text("foo"))
+                .unwrap()
+                .explanation,
+            "Custom pass"
+        );
+    }
+}
@@ -1,12 +1,26 @@
 use std::path::PathBuf;
 
+pub mod annotation;
+pub mod baseline;
+pub mod bloat;
+pub mod build;
+pub mod changes;
+pub mod diff;
+pub mod export;
+pub mod inlining;
+pub mod knowledge;
+pub mod metrics;
 pub mod remark;
 pub mod render;
+pub mod spills;
+pub mod stats;
 pub mod utils;
+pub mod vectorization;
 
 pub const DEFAULT_KIND_FILTER: &[&str] = &["FastISelFailure", "NeverInline", "SpillReloadCopies"];
 
 /// Directory containing Rust sources
+#[derive(Clone)]
 pub struct RustcSourceRoot(pub PathBuf);
 
 impl RustcSourceRoot {
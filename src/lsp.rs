@@ -0,0 +1,404 @@
+//! Language server that publishes remarks as diagnostics of the source files in an editor and
+//! shows their details on hover.
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use crossbeam_channel::Sender;
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::{Notification as _, PublishDiagnostics};
+use lsp_types::request::{HoverRequest, Request as _};
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, InitializeParams, MarkupContent, MarkupKind,
+    NumberOrString, Position, PublishDiagnosticsParams, Range, ServerCapabilities, Url,
+};
+
+use cargo_remark::export::text::SourceCache;
+use cargo_remark::remark::{
+    load_remarks_from_dir, Location, MessagePart, Remark, RemarkLoadOptions,
+};
+use cargo_remark::render::resolve_path;
+use cargo_remark::utils::data_structures::Map;
+
+/// How often is the remark directory checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Remarks grouped by the canonicalized absolute path of their source file.
+#[derive(Default)]
+struct RemarkIndex {
+    files: Map<PathBuf, Vec<Remark>>,
+}
+
+impl RemarkIndex {
+    fn new(remarks: Vec<Remark>, source_dir: &Path) -> Self {
+        let mut files: Map<PathBuf, Vec<Remark>> = Map::default();
+        for remark in remarks {
+            let Some(location) = &remark.function.location else {
+                continue;
+            };
+            files
+                .entry(normalize_path(&resolve_path(
+                    source_dir,
+                    Path::new(&location.file),
+                )))
+                .or_default()
+                .push(remark);
+        }
+        Self { files }
+    }
+}
+
+/// Summary of the remark directory, used to detect that the remarks have been regenerated.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+struct DirFingerprint {
+    files: usize,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl DirFingerprint {
+    fn compute(dir: &Path) -> Self {
+        let mut fingerprint = Self::default();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return fingerprint;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            fingerprint.files += 1;
+            fingerprint.size += metadata.len();
+            fingerprint.modified = fingerprint.modified.max(metadata.modified().ok());
+        }
+        fingerprint
+    }
+}
+
+struct Server {
+    remark_dir: PathBuf,
+    source_dir: PathBuf,
+    options: RemarkLoadOptions,
+    index: Mutex<RemarkIndex>,
+}
+
+impl Server {
+    /// Loads remarks from the remark directory and publishes them as diagnostics.
+    /// Diagnostics of files that do not contain remarks anymore are cleared.
+    fn reload(&self, sender: &Sender<Message>) -> anyhow::Result<()> {
+        let remarks = if self.remark_dir.is_dir() {
+            load_remarks_from_dir(&self.remark_dir, self.options.clone(), None)?
+        } else {
+            log::warn!(
+                "Remark directory {} does not exist yet",
+                self.remark_dir.display()
+            );
+            vec![]
+        };
+        log::info!("Loaded {} remark(s)", remarks.len());
+
+        let index = RemarkIndex::new(remarks, &self.source_dir);
+        let mut previous = self.index.lock().unwrap();
+        for path in previous.files.keys() {
+            if !index.files.contains_key(path) {
+                publish_diagnostics(sender, path, vec![])?;
+            }
+        }
+        let mut cache = SourceCache::new(&self.source_dir);
+        for (path, remarks) in &index.files {
+            let diagnostics = remarks
+                .iter()
+                .map(|remark| create_diagnostic(remark, &self.source_dir, &mut cache))
+                .collect();
+            publish_diagnostics(sender, path, diagnostics)?;
+        }
+        *previous = index;
+        Ok(())
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let path = normalize_path(&position.text_document.uri.to_file_path().ok()?);
+
+        let index = self.index.lock().unwrap();
+        let remarks: Vec<&Remark> = index
+            .files
+            .get(&path)?
+            .iter()
+            .filter(|remark| {
+                remark
+                    .function
+                    .location
+                    .as_ref()
+                    .map(|location| location.line.saturating_sub(1) == position.position.line)
+                    .unwrap_or(false)
+            })
+            .collect();
+        if remarks.is_empty() {
+            return None;
+        }
+
+        let value = remarks
+            .iter()
+            .map(|remark| format_hover(remark, &self.source_dir))
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n");
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: None,
+        })
+    }
+}
+
+/// Runs a language server over stdio, which publishes remarks as diagnostics and shows their
+/// details on hover. The remarks are reloaded whenever the remark directory changes.
+pub fn run_lsp(
+    remark_dir: PathBuf,
+    source_dir: PathBuf,
+    options: RemarkLoadOptions,
+) -> anyhow::Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..Default::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let _params: InitializeParams = serde_json::from_value(params)?;
+
+    let server = Arc::new(Server {
+        remark_dir,
+        source_dir: source_dir.canonicalize().unwrap_or(source_dir),
+        options,
+        index: Mutex::new(RemarkIndex::default()),
+    });
+    server.reload(&connection.sender)?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let watcher = {
+        let server = server.clone();
+        let sender = connection.sender.clone();
+        let stop = stop.clone();
+        std::thread::spawn(move || watch_remark_dir(&server, &sender, &stop))
+    };
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                handle_request(&server, &connection.sender, request)?;
+            }
+            Message::Notification(_) | Message::Response(_) => {}
+        }
+    }
+
+    stop.store(true, Ordering::Relaxed);
+    watcher
+        .join()
+        .expect("Remark directory watcher has panicked");
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn handle_request(
+    server: &Server,
+    sender: &Sender<Message>,
+    request: Request,
+) -> anyhow::Result<()> {
+    let response = if request.method == HoverRequest::METHOD {
+        let params: HoverParams = serde_json::from_value(request.params)?;
+        Response::new_ok(request.id, server.hover(params))
+    } else {
+        Response::new_err(
+            request.id,
+            lsp_server::ErrorCode::MethodNotFound as i32,
+            format!("Unsupported method {}", request.method),
+        )
+    };
+    sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// Reloads remarks once the remark directory has changed and stayed unchanged for one
+/// polling interval, so that remarks are not loaded while they are being generated.
+fn watch_remark_dir(server: &Server, sender: &Sender<Message>, stop: &AtomicBool) {
+    let mut loaded = DirFingerprint::compute(&server.remark_dir);
+    let mut last_seen = loaded;
+    while !stop.load(Ordering::Relaxed) {
+        std::thread::sleep(POLL_INTERVAL);
+        let fingerprint = DirFingerprint::compute(&server.remark_dir);
+        if fingerprint != last_seen {
+            last_seen = fingerprint;
+            continue;
+        }
+        if fingerprint != loaded {
+            loaded = fingerprint;
+            if let Err(error) = server.reload(sender) {
+                log::error!("Cannot reload remarks: {error:?}");
+            }
+        }
+    }
+}
+
+fn publish_diagnostics(
+    sender: &Sender<Message>,
+    path: &Path,
+    diagnostics: Vec<Diagnostic>,
+) -> anyhow::Result<()> {
+    let Ok(uri) = Url::from_file_path(path) else {
+        return Ok(());
+    };
+    let params = PublishDiagnosticsParams {
+        uri,
+        diagnostics,
+        version: None,
+    };
+    sender.send(Message::Notification(Notification::new(
+        PublishDiagnostics::METHOD.to_string(),
+        params,
+    )))?;
+    Ok(())
+}
+
+fn create_diagnostic(remark: &Remark, source_dir: &Path, cache: &mut SourceCache) -> Diagnostic {
+    let related_information = remark
+        .message
+        .iter()
+        .filter_map(|part| match part {
+            MessagePart::AnnotatedString { message, location } => {
+                Some(DiagnosticRelatedInformation {
+                    location: lsp_types::Location {
+                        uri: Url::from_file_path(resolve_path(
+                            source_dir,
+                            Path::new(&location.file),
+                        ))
+                        .ok()?,
+                        range: to_range(location, cache),
+                    },
+                    message: message.clone(),
+                })
+            }
+            MessagePart::String(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    Diagnostic {
+        range: remark
+            .function
+            .location
+            .as_ref()
+            .map(|location| to_range(location, cache))
+            .unwrap_or_default(),
+        severity: Some(DiagnosticSeverity::INFORMATION),
+        code: Some(NumberOrString::String(remark.name.clone())),
+        source: Some("cargo-remark".to_string()),
+        message: remark.message_text(),
+        related_information: (!related_information.is_empty()).then_some(related_information),
+        ..Default::default()
+    }
+}
+
+fn format_hover(remark: &Remark, source_dir: &Path) -> String {
+    let message: String = remark
+        .message
+        .iter()
+        .map(|part| match part {
+            MessagePart::String(message) => message.clone(),
+            MessagePart::AnnotatedString { message, location } => {
+                match Url::from_file_path(resolve_path(source_dir, Path::new(&location.file))) {
+                    Ok(uri) => format!("[`{message}`]({uri}#L{})", location.line),
+                    Err(_) => format!("`{message}`"),
+                }
+            }
+        })
+        .collect();
+
+    let mut hover = format!(
+        "**{}** ({}) in `{}`\n\n{message}",
+        remark.name, remark.pass, remark.function.name
+    );
+    if let Some(hotness) = remark.hotness {
+        hover.push_str(&format!("\n\nHotness: {hotness}"));
+    }
+    hover
+}
+
+/// Canonicalizes the path, so that relative and symlinked paths of the same file are equal.
+/// Paths that do not exist are kept unchanged.
+fn normalize_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Converts a remark location (1-based line and character column) into a LSP range (0-based
+/// line, column in UTF-16 code units). The source line is needed to convert the column, if it
+/// cannot be read, the column is used as-is.
+fn to_range(location: &Location, cache: &mut SourceCache) -> Range {
+    let line = location.line.saturating_sub(1);
+    let column = (location.column as usize).saturating_sub(1);
+    let (start, end) = match cache.line(&location.file, location.line) {
+        Some((_, text)) => {
+            let start: usize = text.chars().take(column).map(char::len_utf16).sum();
+            let width = text.chars().nth(column).map(char::len_utf16).unwrap_or(1);
+            (start, start + width)
+        }
+        None => (column, column + 1),
+    };
+    Range::new(
+        Position::new(line, start as u32),
+        Position::new(line, end as u32),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_remark::export::text::SourceCache;
+    use cargo_remark::remark::Location;
+    use lsp_types::{Position, Range};
+
+    use crate::lsp::to_range;
+
+    #[test]
+    fn range_in_utf16() -> anyhow::Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::write(
+            dir.path().join("main.rs"),
+            "fn main() {\n    let s = \"\u{1F600}é\"; foo(s);\n}\n",
+        )?;
+        let mut cache = SourceCache::new(dir.path());
+        let location = |line, column| Location {
+            file: "main.rs".to_string(),
+            line,
+            column,
+        };
+
+        // `foo` is the 19th character, but the emoji takes two UTF-16 code units
+        assert_eq!(
+            to_range(&location(2, 19), &mut cache),
+            Range::new(Position::new(1, 19), Position::new(1, 20))
+        );
+        // The emoji itself
+        assert_eq!(
+            to_range(&location(2, 14), &mut cache),
+            Range::new(Position::new(1, 13), Position::new(1, 15))
+        );
+        assert_eq!(
+            to_range(&location(1, 4), &mut cache),
+            Range::new(Position::new(0, 3), Position::new(0, 4))
+        );
+        // Files that cannot be read use the column as-is
+        let mut location = location(5, 3);
+        location.file = "missing.rs".to_string();
+        assert_eq!(
+            to_range(&location, &mut cache),
+            Range::new(Position::new(4, 2), Position::new(4, 3))
+        );
+        Ok(())
+    }
+}
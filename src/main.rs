@@ -1,15 +1,33 @@
-mod cargo;
+mod doctor;
+mod lsp;
 
-use cargo::version::check_remark_dir_support;
-use cargo::{get_rustc_source_root, run_cargo, CargoSubcommand};
-use cargo_remark::remark::{load_remarks_from_dir, RemarkLoadOptions};
-use cargo_remark::render::render_remarks;
+use std::path::PathBuf;
+
+use cargo_remark::annotation::expect::{check_expectations, write_expectation_report};
+use cargo_remark::annotation::suppress::{SuppressedRemark, Suppressions};
+use cargo_remark::annotation::{find_annotations, find_source_roots};
+use cargo_remark::baseline::{write_new_remarks, Baseline, DenyFilter};
+use cargo_remark::build::toolchain::Toolchain;
+use cargo_remark::build::{
+    get_cargo_ctx, get_rustc_source_root, BuildOutput, RemarkBuilder, RemarkMode,
+};
+use cargo_remark::changes::filter_changed_since;
+use cargo_remark::diff::{diff_remark_dirs, write_diff_text};
+use cargo_remark::export::diagnostic::write_compiler_messages;
+use cargo_remark::export::json::write_diff_json;
+use cargo_remark::export::{export_remarks, open_output, sort_remarks, OutputFormat, RemarkSort};
+use cargo_remark::knowledge::KnowledgeBase;
+use cargo_remark::remark::{Remark, RemarkLoadOptions, RemarkType};
+use cargo_remark::render::{render_diff, render_remarks, DIFF_FILE_PATH};
 use cargo_remark::utils::callback::ProgressBarCallback;
-use cargo_remark::utils::cli::cli_format_path;
-use cargo_remark::utils::open_result;
+use cargo_remark::utils::cli::{cli_format_path, configure_output_colors};
 use cargo_remark::utils::timing::time_block_log_info;
+use cargo_remark::utils::{open_page, open_result};
 use clap::Parser;
+use doctor::run_doctor;
 use env_logger::Env;
+use lsp::run_lsp;
+use regex::Regex;
 
 #[cfg(feature = "mimalloc")]
 #[global_allocator]
@@ -32,21 +50,210 @@ enum Subcommand {
     Build(SharedArgs),
     /// Wrap an arbitrary cargo command, while configuring it to generate remarks.
     Wrap(SharedArgs),
+    /// Check the environment used to generate remarks and suggest fixes for found problems.
+    Doctor(DoctorArgs),
+    /// Build a crate and compare its remarks with a baseline of known remarks.
+    /// Fails if new remarks are found, which is useful for detecting regressions on CI.
+    Check(CheckArgs),
+    /// Run a language server over stdio, which shows remarks as diagnostics in editors.
+    Lsp(LspArgs),
+    /// Compare remarks from two directories with YAML remarks (e.g. from two different builds).
+    Diff(DiffArgs),
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum DiffFormat {
+    /// Print the differences to the terminal.
+    Text,
+    /// Print the differences to stdout as JSON.
+    Json,
+    /// Render a website with the differences.
+    Html,
+}
+
+#[derive(clap::Parser, Debug)]
+struct DiffArgs {
+    /// Directory with YAML remarks of the old build.
+    old_dir: PathBuf,
+
+    /// Directory with YAML remarks of the new build.
+    new_dir: PathBuf,
+
+    /// Root directory of source (crate) from which the remarks were generated.
+    /// The root of the current Cargo workspace is used by default.
+    #[arg(long)]
+    source_dir: Option<PathBuf>,
+
+    /// Load remarks from external code (i.e. crate dependencies).
+    #[arg(long)]
+    external: bool,
+
+    /// Optimization remark kinds that should be ignored.
+    #[arg(
+        long = "filter",
+        value_delimiter = ',',
+        default_values = cargo_remark::DEFAULT_KIND_FILTER
+    )]
+    filter_kind: Vec<String>,
+
+    /// Output format of the differences.
+    #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+    format: DiffFormat,
+
+    /// Output directory for the `html` format.
+    /// `target/remarks/diff` is used by default.
+    #[arg(long)]
+    output_dir: Option<PathBuf>,
+
+    /// Open the generated website after the diff is rendered.
+    #[arg(long)]
+    open: bool,
+}
+
+#[derive(clap::Parser, Debug)]
+struct LspArgs {
+    /// Directory with YAML remarks.
+    /// `target/remarks/yaml` of the current Cargo workspace is used by default.
+    #[arg(long)]
+    remark_dir: Option<PathBuf>,
+
+    /// Root directory of source (crate) from which the remarks were generated.
+    /// The root of the current Cargo workspace is used by default.
+    #[arg(long)]
+    source_dir: Option<PathBuf>,
+
+    /// Load remarks from external code (i.e. crate dependencies).
+    #[arg(long)]
+    external: bool,
+
+    /// Optimization remark kinds that should be ignored.
+    /// When remarks are parsed from diagnostics of a stable compiler, the remark kinds are not
+    /// available and the filter matches LLVM pass names (e.g. `inline`) instead.
+    #[arg(
+        long = "filter",
+        value_delimiter = ',',
+        default_values = cargo_remark::DEFAULT_KIND_FILTER
+    )]
+    filter_kind: Vec<String>,
+}
+
+#[derive(clap::Parser, Debug)]
+struct DoctorArgs {
+    /// Rustup toolchain that will be checked (e.g. `nightly`).
+    #[arg(long)]
+    toolchain: Option<String>,
+
+    /// Target triple for which the standard library sources will be resolved.
+    #[arg(long)]
+    target: Option<String>,
+
+    /// Cargo profile whose settings will be checked.
+    #[arg(long, default_value = "release")]
+    profile: String,
 }
 
 #[derive(clap::Parser, Debug)]
-#[clap(trailing_var_arg = true)]
 struct SharedArgs {
     /// Open the generated website after the build finishes.
     #[arg(long)]
     open: bool,
 
+    /// Output format of the remarks.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Html)]
+    format: OutputFormat,
+
+    /// File into which the remarks will be written, for formats other than `html`.
+    /// The remarks are written to stdout by default.
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Order of the remarks, for formats other than `html`.
+    #[arg(long, value_enum, default_value_t = RemarkSort::File)]
+    sort: RemarkSort,
+
+    /// Show suppressed remarks greyed out in the remark list of the website.
+    #[arg(long)]
+    show_suppressed: bool,
+
+    /// Also load passed remarks (applied optimizations), e.g. vectorized loops.
+    #[arg(long)]
+    passed: bool,
+
+    /// Also load analysis remarks, which e.g. explain why a loop was not vectorized.
+    /// Always enabled for the `metrics-json` format.
+    #[arg(long)]
+    analysis: bool,
+
+    /// File with explanations of remark kinds, which extend and override the built-in ones.
+    /// `remark-knowledge.toml` in the workspace root is used by default, if it exists.
+    #[arg(long)]
+    knowledge_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    build: BuildArgs,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum MessageFormat {
+    /// Print a human-readable report.
+    Human,
+    /// Print the remarks as Cargo JSON compiler messages, which can be consumed by editors.
+    Json,
+}
+
+#[derive(clap::Parser, Debug)]
+struct CheckArgs {
+    /// Baseline file with known remarks.
+    /// The check fails if a new remark that is not present in the baseline is generated.
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// Save the generated remarks as a baseline into the given file.
+    #[arg(long)]
+    save_baseline: Option<PathBuf>,
+
+    /// Check `// remark: expect-*` annotations in the source code of the workspace.
+    /// The check fails if any expectation is broken.
+    #[arg(long)]
+    expectations: bool,
+
+    /// Only fail on new remarks of these kinds (e.g. `NoDefinition`).
+    /// If neither `--deny-kind` nor `--deny-function` is used, all new remarks fail the check.
+    #[arg(long, value_delimiter = ',')]
+    deny_kind: Vec<String>,
+
+    /// Only fail on new remarks in functions matching this regular expression.
+    /// Can be used multiple times.
+    #[arg(long)]
+    deny_function: Vec<Regex>,
+
+    /// Format of the reported remarks.
+    /// With `json`, remarks are printed as Cargo `compiler-message` JSON objects, so that the
+    /// command can be used as a custom check command in editors. If a baseline is used, only new
+    /// remarks are printed.
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+
+    #[command(flatten)]
+    build: BuildArgs,
+}
+
+#[derive(clap::Parser, Debug)]
+struct BuildArgs {
     /// Load remarks from external code (i.e. crate dependencies).
     /// Note that this may produce a large amount of data!
     #[arg(long)]
     external: bool,
 
+    /// Rustup toolchain that will be used to build the crate (e.g. `nightly`).
+    /// By default, the toolchain is resolved by rustup (respecting `rust-toolchain.toml`).
+    /// The `RUSTC` environment variable is also respected.
+    #[arg(long)]
+    toolchain: Option<String>,
+
     /// Optimization remark kinds that should be ignored.
+    /// When remarks are parsed from diagnostics of a stable compiler, the remark kinds are not
+    /// available and the filter matches LLVM pass names (e.g. `inline`) instead.
     #[arg(
         long = "filter",
         value_delimiter = ',',
@@ -54,25 +261,57 @@ struct SharedArgs {
     )]
     filter_kind: Vec<String>,
 
+    /// File with acknowledged remarks, which will be suppressed.
+    /// `remark-ack.toml` in the workspace root is used by default, if it exists.
+    #[arg(long)]
+    ack_file: Option<PathBuf>,
+
+    /// Only keep remarks located on lines that were changed since the given git revision
+    /// (e.g. `origin/main`), or that mention such lines. Uncommitted changes are included.
+    #[arg(long, value_name = "REV")]
+    changed_since: Option<String>,
+
     /// Additional arguments that will be passed to Cargo.
+    #[arg(trailing_var_arg = true)]
     cargo_args: Vec<String>,
 }
 
-fn generate_remarks(subcmd: CargoSubcommand, args: SharedArgs) -> anyhow::Result<()> {
-    let SharedArgs {
-        open,
+/// Builds the crate and loads the generated remarks.
+/// Passed and analysis remarks are only loaded if `passed` or `analysis` is set. Remarks that are
+/// only used by the website (remarks without a location and filtered spill remarks) are only
+/// loaded if `website` is set.
+/// Remarks suppressed by `allow` annotations or by the acknowledgement file are returned
+/// separately. If `strict` is set, a failure to load the suppressions is an error, otherwise it
+/// is only reported as a warning.
+fn build_remarks(
+    builder: RemarkBuilder,
+    args: BuildArgs,
+    passed: bool,
+    analysis: bool,
+    website: bool,
+    strict: bool,
+) -> anyhow::Result<(BuildOutput, Vec<Remark>, Vec<SuppressedRemark>)> {
+    let BuildArgs {
         external,
+        toolchain,
         filter_kind,
+        ack_file,
+        changed_since,
         cargo_args,
     } = args;
-    if !check_remark_dir_support()? {
-        return Err(anyhow::anyhow!(
-            "Your version of rustc does not support `-Zremark-dir`. Please use a nightly version newer than 4. 7. 2023."
-        ));
+    let toolchain = Toolchain::new(toolchain, &cargo_args);
+    let mode = RemarkMode::detect(&toolchain)?;
+    if let RemarkMode::Diagnostics = mode {
+        log::warn!("Your version of rustc does not support `-Zremark-dir`, falling back to parsing remarks from diagnostics. Remarks will not contain remark kinds, hotness and some function names. `--filter` matches pass names instead of remark kinds in this mode. Use a nightly version newer than 4. 7. 2023 to get complete remarks.");
     }
-    let output = run_cargo(subcmd, cargo_args)?;
+    let rustc_source_root = get_rustc_source_root(&toolchain);
+    let output = builder
+        .cargo_args(cargo_args)
+        .toolchain(toolchain)
+        .mode(mode)
+        .build()?;
 
-    let rustc_source_root = match get_rustc_source_root() {
+    let rustc_source_root = match rustc_source_root {
         Ok(root) => Some(root),
         Err(error) => {
             log::warn!("Cannot find rustc source root: {error:?}");
@@ -80,33 +319,304 @@ fn generate_remarks(subcmd: CargoSubcommand, args: SharedArgs) -> anyhow::Result
         }
     };
 
+    let options = RemarkLoadOptions {
+        external,
+        source_dir: output.source_dir.clone(),
+        filter_kind,
+        rustc_source_root,
+        passed,
+        analysis,
+        unlocated: website,
+        spills: website,
+    };
     let remarks = time_block_log_info("Remark loading", || {
-        load_remarks_from_dir(
-            output.yaml_dir,
+        output.load_remarks(options, Some(&ProgressBarCallback::default()))
+    })?;
+
+    let source_roots = find_source_roots(&output.metadata);
+    let suppressions = if strict {
+        Suppressions::load(&output.source_dir, &source_roots, ack_file.as_deref())?
+    } else {
+        Suppressions::load_or_warn(&output.source_dir, &source_roots, ack_file.as_deref())
+    };
+    let (mut remarks, mut suppressed) = suppressions.partition(remarks);
+    if let Some(revision) = changed_since {
+        filter_changed_since(
+            &output.source_dir,
+            &revision,
+            &mut remarks,
+            Some(&mut suppressed),
+        )?;
+    }
+    if !suppressed.is_empty() {
+        log::info!("{} remark(s) suppressed", suppressed.len());
+    }
+    Ok((output, remarks, suppressed))
+}
+
+fn generate_remarks(builder: RemarkBuilder, args: SharedArgs) -> anyhow::Result<()> {
+    let SharedArgs {
+        open,
+        format,
+        output: output_path,
+        sort,
+        show_suppressed,
+        passed,
+        analysis,
+        knowledge_file,
+        build,
+    } = args;
+    let (output, mut remarks, suppressed) = build_remarks(
+        builder,
+        build,
+        passed,
+        analysis || format == OutputFormat::MetricsJson,
+        format == OutputFormat::Html,
+        false,
+    )?;
+    let knowledge = KnowledgeBase::load(&output.source_dir, knowledge_file.as_deref())?;
+    match format {
+        OutputFormat::Html => {
+            time_block_log_info("Rendering", || {
+                render_remarks(
+                    remarks,
+                    &suppressed,
+                    show_suppressed,
+                    &knowledge,
+                    &output.source_dir,
+                    &output.web_dir,
+                    Some(&ProgressBarCallback::default()),
+                )
+            })?;
+
+            log::info!("Website built into {}.", cli_format_path(&output.web_dir));
+
+            open_result(&output.web_dir, open)?;
+        }
+        format => {
+            sort_remarks(&mut remarks, sort);
+            configure_output_colors(output_path.as_deref());
+            export_remarks(
+                &remarks,
+                format,
+                &output.source_dir,
+                &knowledge,
+                open_output(output_path.as_deref())?,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn check_remarks(args: CheckArgs) -> anyhow::Result<()> {
+    let CheckArgs {
+        baseline,
+        save_baseline,
+        expectations,
+        deny_kind,
+        deny_function,
+        message_format,
+        mut build,
+    } = args;
+    if let MessageFormat::Human = message_format {
+        if baseline.is_none() && save_baseline.is_none() && !expectations {
+            return Err(anyhow::anyhow!(
+                "Nothing to check, use `--baseline`, `--save-baseline`, `--expectations` or `--message-format json`"
+            ));
+        }
+    }
+    let baseline = baseline.map(|path| Baseline::load(&path)).transpose()?;
+
+    // Expectations can refer to remark kinds that are filtered by default and to unchanged code,
+    // so the remarks are loaded without the kind and change filters, which are applied afterwards.
+    // Passed remarks are only loaded to check that expected optimizations have happened.
+    let filter_kind = std::mem::take(&mut build.filter_kind);
+    let changed_since = build.changed_since.take();
+    let (output, unsuppressed, suppressed) = build_remarks(
+        RemarkBuilder::new(),
+        build,
+        expectations,
+        false,
+        false,
+        true,
+    )?;
+    let mut remarks: Vec<Remark> = unsuppressed
+        .iter()
+        .filter(|remark| remark.remark_type == RemarkType::Missed)
+        .filter(|remark| !filter_kind.contains(&remark.name))
+        .cloned()
+        .collect();
+    // The baseline contains remarks from the whole workspace, not only from changed lines
+    if let Some(path) = save_baseline {
+        Baseline::from_remarks(&remarks).save(&path)?;
+        log::info!("Baseline saved into {}.", cli_format_path(&path));
+    }
+    if let Some(revision) = changed_since {
+        filter_changed_since(&output.source_dir, &revision, &mut remarks, None)?;
+    }
+
+    let reported: Vec<&Remark> = match &baseline {
+        Some(baseline) => {
+            let filter = DenyFilter {
+                kinds: deny_kind,
+                functions: deny_function,
+            };
+            baseline
+                .find_new_remarks(&remarks)
+                .into_iter()
+                .filter(|remark| filter.is_denied(remark))
+                .collect()
+        }
+        None => remarks.iter().collect(),
+    };
+
+    match message_format {
+        MessageFormat::Human => {
+            if baseline.is_some() && !reported.is_empty() {
+                write_new_remarks(&reported, std::io::stdout().lock())?;
+            }
+        }
+        MessageFormat::Json => {
+            colored::control::set_override(false);
+            write_compiler_messages(
+                reported.iter().copied(),
+                &output.source_dir,
+                &output.metadata,
+                std::io::stdout().lock(),
+            )?;
+        }
+    }
+
+    let mut failures = vec![];
+    if baseline.is_some() {
+        if reported.is_empty() {
+            log::info!("No new remarks have been found.");
+        } else {
+            failures.push(format!("{} new remark(s) have been found", reported.len()));
+        }
+    }
+
+    if expectations {
+        let annotations =
+            find_annotations(&output.source_dir, &find_source_roots(&output.metadata))?;
+        // Expectations also see suppressed remarks
+        let all_remarks: Vec<Remark> = unsuppressed
+            .into_iter()
+            .chain(suppressed.into_iter().map(|suppressed| suppressed.remark))
+            .collect();
+        let broken = check_expectations(&annotations, &all_remarks);
+        let checked = annotations
+            .iter()
+            .filter(|annotation| annotation.directive.is_expectation())
+            .count();
+        // Keep stdout reserved for JSON messages
+        match message_format {
+            MessageFormat::Human => {
+                write_expectation_report(&broken, checked, std::io::stdout().lock())?
+            }
+            MessageFormat::Json => {
+                write_expectation_report(&broken, checked, std::io::stderr().lock())?
+            }
+        }
+        if !broken.is_empty() {
+            failures.push(format!("{} expectation(s) have been broken", broken.len()));
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("{}", failures.join(", ")))
+    }
+}
+
+fn diff_remarks(args: DiffArgs) -> anyhow::Result<()> {
+    let DiffArgs {
+        old_dir,
+        new_dir,
+        source_dir,
+        external,
+        filter_kind,
+        format,
+        output_dir,
+        open,
+    } = args;
+
+    let ctx = get_cargo_ctx(None, None).ok();
+    let source_dir = source_dir
+        .or_else(|| ctx.as_ref().map(|ctx| ctx.root_directory.clone()))
+        .unwrap_or_else(|| PathBuf::from("."));
+    let diff = time_block_log_info("Remark loading", || {
+        diff_remark_dirs(
+            old_dir,
+            new_dir,
             RemarkLoadOptions {
                 external,
-                source_dir: output.source_dir.clone(),
+                source_dir,
                 filter_kind,
-                rustc_source_root,
+                rustc_source_root: None,
+                passed: false,
+                analysis: false,
+                unlocated: false,
+                spills: false,
             },
-            Some(&ProgressBarCallback::default()),
-        )
-    })?;
-    time_block_log_info("Rendering", || {
-        render_remarks(
-            remarks,
-            &output.source_dir,
-            &output.web_dir,
-            Some(&ProgressBarCallback::default()),
         )
     })?;
 
-    log::info!("Website built into {}.", cli_format_path(&output.web_dir));
-
-    open_result(&output.web_dir, open)?;
+    match format {
+        DiffFormat::Text => write_diff_text(&diff, std::io::stdout().lock())?,
+        DiffFormat::Json => write_diff_json(&diff, std::io::stdout().lock())?,
+        DiffFormat::Html => {
+            let output_dir = output_dir
+                .or_else(|| {
+                    ctx.as_ref()
+                        .map(|ctx| ctx.target_directory.join("remarks").join("diff"))
+                })
+                .unwrap_or_else(|| PathBuf::from("remark-diff"));
+            render_diff(&diff, &output_dir)?;
+            open_page(&output_dir.join(DIFF_FILE_PATH), open)?;
+        }
+    }
     Ok(())
 }
 
+fn run_lsp_server(args: LspArgs) -> anyhow::Result<()> {
+    let LspArgs {
+        remark_dir,
+        source_dir,
+        external,
+        filter_kind,
+    } = args;
+
+    let ctx = get_cargo_ctx(None, None).ok();
+    let source_dir = source_dir
+        .or_else(|| ctx.as_ref().map(|ctx| ctx.root_directory.clone()))
+        .unwrap_or_else(|| PathBuf::from("."));
+    let remark_dir = match remark_dir {
+        Some(dir) => dir,
+        None => ctx
+            .as_ref()
+            .map(|ctx| ctx.target_directory.join("remarks").join("yaml"))
+            .ok_or_else(|| {
+                anyhow::anyhow!("Cannot find Cargo workspace, use `--remark-dir` explicitly")
+            })?,
+    };
+    run_lsp(
+        remark_dir,
+        source_dir.clone(),
+        RemarkLoadOptions {
+            external,
+            source_dir,
+            filter_kind,
+            rustc_source_root: None,
+            passed: false,
+            analysis: false,
+            unlocated: false,
+            spills: false,
+        },
+    )
+}
+
 fn main() -> anyhow::Result<()> {
     env_logger::Builder::from_env(Env::default().default_filter_or("cargo_remark=info")).init();
 
@@ -114,8 +624,21 @@ fn main() -> anyhow::Result<()> {
 
     match args {
         Args::Remark(args) => match args {
-            Subcommand::Build(args) => generate_remarks(CargoSubcommand::Build, args),
-            Subcommand::Wrap(args) => generate_remarks(CargoSubcommand::Wrap, args),
+            Subcommand::Build(args) => generate_remarks(RemarkBuilder::new(), args),
+            Subcommand::Wrap(args) => generate_remarks(RemarkBuilder::wrap(), args),
+            Subcommand::Doctor(args) => {
+                let DoctorArgs {
+                    toolchain,
+                    target,
+                    profile,
+                } = args;
+                let mut toolchain = Toolchain::new(toolchain, &[]);
+                toolchain.target = target.or(toolchain.target);
+                run_doctor(toolchain, profile)
+            }
+            Subcommand::Check(args) => check_remarks(args),
+            Subcommand::Lsp(args) => run_lsp_server(args),
+            Subcommand::Diff(args) => diff_remarks(args),
         },
     }
 }
@@ -0,0 +1,108 @@
+//! Per-function metrics (stack size and instruction count), parsed from analysis remarks emitted
+//! by the code generator for every function.
+use crate::remark::{Function, Remark, RemarkType};
+use crate::utils::data_structures::Map;
+
+/// Remark with the size of the stack frame (e.g. `24 stack bytes in function`).
+const STACK_SIZE_REMARK: (&str, &str) = ("prologepilog", "StackSize");
+/// Remark with the number of machine instructions (e.g. `5 instructions in function`).
+const INSTRUCTION_COUNT_REMARK: (&str, &str) = ("asm-printer", "InstructionCount");
+
+#[derive(Debug, Clone)]
+pub struct FunctionMetrics {
+    pub function: Function,
+    /// Size of the stack frame in bytes.
+    pub stack_size: Option<u64>,
+    /// Number of machine instructions.
+    pub instruction_count: Option<u64>,
+}
+
+/// Computes metrics of all functions that have a `StackSize` or `InstructionCount` remark.
+/// If a function was generated multiple times (e.g. in several codegen units), the largest value
+/// is used. The metrics are sorted by function name.
+pub fn compute_metrics(remarks: &[Remark]) -> Vec<FunctionMetrics> {
+    let mut metrics: Map<&str, FunctionMetrics> = Map::default();
+    for remark in remarks {
+        if remark.remark_type != RemarkType::Analysis {
+            continue;
+        }
+        let kind = (remark.pass.as_str(), remark.name.as_str());
+        // Remarks parsed from text diagnostics do not have a name, only the pass
+        let is_kind =
+            |(pass, name): (&str, &str)| kind.0 == pass && (kind.1 == name || kind.1 == pass);
+        let is_stack_size = is_kind(STACK_SIZE_REMARK);
+        if !is_stack_size && !is_kind(INSTRUCTION_COUNT_REMARK) {
+            continue;
+        }
+        let Some(value) = leading_number(&remark.message_text()) else {
+            continue;
+        };
+
+        let key = remark
+            .function
+            .mangled_name
+            .as_deref()
+            .unwrap_or(&remark.function.name);
+        let entry = metrics.entry(key).or_insert_with(|| FunctionMetrics {
+            function: remark.function.clone(),
+            stack_size: None,
+            instruction_count: None,
+        });
+        let metric = if is_stack_size {
+            &mut entry.stack_size
+        } else {
+            &mut entry.instruction_count
+        };
+        *metric = (*metric).max(Some(value));
+    }
+
+    let mut metrics: Vec<FunctionMetrics> = metrics.into_values().collect();
+    metrics.sort_by(|a, b| {
+        (&a.function.name, &a.function.mangled_name)
+            .cmp(&(&b.function.name, &b.function.mangled_name))
+    });
+    metrics
+}
+
+fn leading_number(text: &str) -> Option<u64> {
+    let text = text.trim_start();
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::compute_metrics;
+    use crate::remark::fixtures::remark;
+    use crate::remark::{RemarkStage, RemarkType};
+
+    #[test]
+    fn function_metrics() {
+        let analysis = |pass: &str, name: &str, function: &str| {
+            remark(pass, name)
+                .in_function(function)
+                .unlocated()
+                .with_stage(RemarkStage::Codegen)
+                .with_type(RemarkType::Analysis)
+        };
+        let remarks = vec![
+            analysis("prologepilog", "StackSize", "foo::main")
+                .with_text("24 stack bytes in function"),
+            analysis("asm-printer", "InstructionCount", "foo::main")
+                .with_text("5 instructions in function"),
+            analysis("asm-printer", "InstructionCount", "foo::main")
+                .with_text("7 instructions in function"),
+            analysis("prologepilog", "StackSize", "foo::bar")
+                .with_text("4096 stack bytes in function"),
+            analysis("size-info", "FunctionMISizeChange", "foo::bar")
+                .with_text("1 instruction changed"),
+        ];
+        let metrics = compute_metrics(&remarks);
+        insta::assert_snapshot!(metrics.iter().map(|metrics| format!("{}: {:?} {:?}", metrics.function.name, metrics.stack_size, metrics.instruction_count)).collect::<Vec<_>>().join("\n"), @r###"
+        foo::bar: Some(4096) None
+        foo::main: Some(24) Some(7)
+        "###);
+    }
+}
@@ -0,0 +1,141 @@
+//! Classification of remarks that are caused by panic paths, e.g. calls to
+//! `core::panicking::panic_bounds_check`. Such remarks can often be resolved by small changes of
+//! Rust code, e.g. by using iterators instead of indexing.
+use crate::remark::{CallEdge, MessagePart};
+
+/// Functions that panic when an index or a range is out of bounds.
+const BOUNDS_CHECK_FUNCTIONS: &[&str] = &[
+    "panic_bounds_check",
+    "slice_index_len_fail",
+    "slice_start_index_len_fail",
+    "slice_end_index_len_fail",
+    "slice_index_order_fail",
+    "slice_start_index_overflow_fail",
+    "slice_end_index_overflow_fail",
+    "slice_error_fail",
+    "len_mismatch_fail",
+];
+
+/// Functions (or parts of their paths) that start a panic.
+const PANIC_FUNCTIONS: &[&str] = &[
+    "::panicking::",
+    "unwrap_failed",
+    "expect_failed",
+    "begin_panic",
+    "assert_failed",
+    "capacity_overflow",
+];
+
+/// Category of a remark that involves a panic path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RemarkCategory {
+    /// The remark involves a panic caused by an out of bounds index or range.
+    BoundsCheck,
+    /// The remark involves another panic (e.g. `unwrap`, `expect` or an overflow check).
+    PanicPath,
+}
+
+impl RemarkCategory {
+    /// Classifies a remark based on its callee and on the functions mentioned (annotated) in its
+    /// message. Plain text of the message is ignored, because it also contains e.g. the name of
+    /// the caller.
+    pub fn classify(call: Option<&CallEdge>, message: &[MessagePart]) -> Option<Self> {
+        let texts =
+            call.map(|call| call.callee.as_str())
+                .into_iter()
+                .chain(message.iter().filter_map(|part| match part {
+                    MessagePart::String(_) => None,
+                    MessagePart::AnnotatedString { message, .. } => Some(message.as_str()),
+                }));
+        let mut category = None;
+        for text in texts {
+            if BOUNDS_CHECK_FUNCTIONS
+                .iter()
+                .any(|name| text.contains(name))
+            {
+                return Some(Self::BoundsCheck);
+            }
+            if PANIC_FUNCTIONS.iter().any(|name| text.contains(name)) {
+                category = Some(Self::PanicPath);
+            }
+        }
+        category
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BoundsCheck => "bounds-check",
+            Self::PanicPath => "panic-path",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::BoundsCheck => "Bounds check",
+            Self::PanicPath => "Panic path",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remark::category::RemarkCategory;
+    use crate::remark::fixtures;
+    use crate::remark::CallEdge;
+
+    #[test]
+    fn classify_callee() {
+        let call = |callee: &str| CallEdge {
+            caller: "foo::main".to_string(),
+            callee: callee.to_string(),
+            cost: None,
+            threshold: None,
+        };
+        assert_eq!(
+            RemarkCategory::classify(Some(&call("core::panicking::panic_bounds_check")), &[]),
+            Some(RemarkCategory::BoundsCheck)
+        );
+        assert_eq!(
+            RemarkCategory::classify(
+                Some(&call("core::slice::index::slice_end_index_len_fail")),
+                &[]
+            ),
+            Some(RemarkCategory::BoundsCheck)
+        );
+        assert_eq!(
+            RemarkCategory::classify(Some(&call("core::result::unwrap_failed")), &[]),
+            Some(RemarkCategory::PanicPath)
+        );
+        assert_eq!(RemarkCategory::classify(Some(&call("foo::bar")), &[]), None);
+    }
+
+    #[test]
+    fn classify_message() {
+        let location = || fixtures::location("src/panicking.rs", 1, 1);
+        let message = vec![
+            fixtures::annotated("core::panicking::panic_fmt", location()),
+            fixtures::text(" will not be inlined into foo::main"),
+        ];
+        assert_eq!(
+            RemarkCategory::classify(None, &message),
+            Some(RemarkCategory::PanicPath)
+        );
+        // Bounds checks take precedence over other panics
+        let message = vec![
+            fixtures::annotated("core::panicking::panic_fmt", location()),
+            fixtures::text(" and "),
+            fixtures::annotated("core::panicking::panic_bounds_check", location()),
+        ];
+        assert_eq!(
+            RemarkCategory::classify(None, &message),
+            Some(RemarkCategory::BoundsCheck)
+        );
+        // The caller is not a panic path
+        let message = vec![
+            fixtures::annotated("foo::bar", location()),
+            fixtures::text(" will not be inlined into core::result::unwrap_failed"),
+        ];
+        assert_eq!(RemarkCategory::classify(None, &message), None);
+    }
+}
@@ -0,0 +1,125 @@
+//! Remarks used by unit tests.
+//! Tests start from a default remark and chain the builder methods below for the fields that they
+//! care about, e.g. `remark("inline", "NoDefinition").at("src/main.rs", 10).with_hotness(5)`.
+//! Fields without a builder method can be set with the struct update syntax.
+use crate::remark::{
+    CallEdge, Column, Function, Line, Location, MessagePart, Remark, RemarkStage, RemarkType,
+    SpillCounts,
+};
+
+/// Creates a missed pre-link remark located in `foo::main` at `src/main.rs:1:5`, with a single
+/// `message` text part.
+pub fn remark(pass: &str, name: &str) -> Remark {
+    Remark {
+        pass: pass.to_string(),
+        name: name.to_string(),
+        function: function("foo::main", Some(location("src/main.rs", 1, 5))),
+        message: vec![MessagePart::String("message".to_string())],
+        hotness: None,
+        stage: RemarkStage::PreLink,
+        remark_type: RemarkType::Missed,
+        call: None,
+        spills: None,
+        category: None,
+        filtered: false,
+    }
+}
+
+impl Remark {
+    pub fn in_function(mut self, name: &str) -> Self {
+        self.function.name = name.to_string();
+        self
+    }
+
+    /// Moves the remark to the given line of `file`, at column 5.
+    pub fn at(mut self, file: &str, line: Line) -> Self {
+        self.function.location = Some(location(file, line, 5));
+        self
+    }
+
+    pub fn unlocated(mut self) -> Self {
+        self.function.location = None;
+        self
+    }
+
+    pub fn with_message(mut self, message: Vec<MessagePart>) -> Self {
+        self.message = message;
+        self
+    }
+
+    /// Replaces the message with a single text part.
+    pub fn with_text(self, message: &str) -> Self {
+        self.with_message(vec![text(message)])
+    }
+
+    pub fn with_hotness(mut self, hotness: i32) -> Self {
+        self.hotness = Some(hotness);
+        self
+    }
+
+    pub fn with_stage(mut self, stage: RemarkStage) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    pub fn with_type(mut self, remark_type: RemarkType) -> Self {
+        self.remark_type = remark_type;
+        self
+    }
+
+    /// Sets a call from the function of the remark to `callee`, without a cost.
+    pub fn calling(mut self, callee: &str) -> Self {
+        self.call = Some(CallEdge {
+            caller: self.function.name.clone(),
+            callee: callee.to_string(),
+            cost: None,
+            threshold: None,
+        });
+        self
+    }
+
+    /// Sets the inlining cost of the call, which has to be set with [`Remark::calling`] first.
+    pub fn with_cost(mut self, cost: &str, threshold: &str) -> Self {
+        let call = self.call.as_mut().expect("remark without a call");
+        call.cost = Some(cost.to_string());
+        call.threshold = Some(threshold.to_string());
+        self
+    }
+
+    pub fn with_spills(mut self, spills: u64, reloads: u64, copies: u64) -> Self {
+        self.spills = Some(SpillCounts {
+            spills,
+            reloads,
+            copies,
+        });
+        self
+    }
+}
+
+pub fn function(name: &str, location: Option<Location>) -> Function {
+    Function {
+        name: name.to_string(),
+        mangled_name: None,
+        location,
+    }
+}
+
+pub fn location(file: &str, line: Line, column: Column) -> Location {
+    Location {
+        file: file.to_string(),
+        line,
+        column,
+    }
+}
+
+pub fn text(text: &str) -> MessagePart {
+    MessagePart::String(text.to_string())
+}
+
+/// Creates a message part that mentions a function defined at the given location.
+pub fn annotated(text: &str, location: Location) -> MessagePart {
+    MessagePart::AnnotatedString {
+        message: text.to_string(),
+        location,
+    }
+}
@@ -1,22 +1,30 @@
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Write};
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
 use anyhow::Context;
+use colored::{ColoredString, Colorize};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use regex::Regex;
 use serde::Deserialize;
 use serde_yaml::Value;
 
-use crate::remark::parse::{MissedRemark, RemarkArg, RemarkArgCallee, RemarkArgCaller};
+pub use crate::remark::category::RemarkCategory;
+use crate::remark::parse::{RemarkArg, RemarkArgCallee, RemarkArgCaller, RemarkData};
+use crate::spills::{FUNCTION_SPILLS_REMARK, LOOP_SPILLS_REMARK};
 use crate::utils::callback::LoadCallback;
+use crate::utils::data_structures::Set;
 use crate::utils::timing::time_block_log_debug;
 use crate::RustcSourceRoot;
 
+pub mod category;
+#[cfg(test)]
+pub(crate) mod fixtures;
 mod parse;
+pub mod text;
 
 /// We expect that the remark YAML files will have this extension.
 const EXPECTED_EXTENSION: &str = ".opt.yaml";
@@ -24,35 +32,186 @@ const EXPECTED_EXTENSION: &str = ".opt.yaml";
 pub type Line = u32;
 pub type Column = u32;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct Location {
     pub file: String,
     pub line: Line,
     pub column: Column,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct Function {
+    /// Demangled name of the function.
     pub name: String,
+    /// Symbol name of the function, if it is known.
+    pub mangled_name: Option<String>,
     pub location: Option<Location>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(untagged)]
 pub enum MessagePart {
     String(String),
     AnnotatedString { message: String, location: Location },
 }
 
-#[derive(Debug)]
+/// Compilation stage during which was a remark emitted.
+/// It is inferred from the name of the remark file generated by `-Zremark-dir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RemarkStage {
+    /// Optimization pipeline that runs on each codegen unit before linking.
+    PreLink,
+    /// Link-time optimization pipeline (fat or thin LTO).
+    Lto,
+    /// Code generation (instruction selection, register allocation, etc.).
+    Codegen,
+    /// The remark file name does not follow the naming scheme used by `rustc`.
+    Unknown,
+}
+
+impl RemarkStage {
+    /// `rustc` stores remarks into files named `<module>.<stage>.opt.yaml`.
+    pub fn from_path(path: &Path) -> Self {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return Self::Unknown;
+        };
+        match name
+            .strip_suffix(EXPECTED_EXTENSION)
+            .and_then(|name| name.rsplit_once('.'))
+        {
+            Some((_, "opt")) => Self::PreLink,
+            Some((_, "lto")) => Self::Lto,
+            Some((_, "codegen")) => Self::Codegen,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PreLink => "pre-link",
+            Self::Lto => "lto",
+            Self::Codegen => "codegen",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Call of a function that is described by a remark (e.g. a call that was not inlined).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+pub struct CallEdge {
+    /// Demangled name of the calling function.
+    pub caller: String,
+    /// Demangled name of the called function.
+    pub callee: String,
+    /// Inlining cost of the call, computed by the inliner (e.g. `120` or `never`).
+    pub cost: Option<String>,
+    /// Maximum cost of a call that is still inlined.
+    pub threshold: Option<String>,
+}
+
+/// Spill, reload and copy instructions generated by the register allocator in a function or in a
+/// loop, described by a `SpillReloadCopies` or `LoopSpillReloadCopies` remark.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct SpillCounts {
+    /// Number of spills, including spills folded into other instructions.
+    pub spills: u64,
+    /// Number of reloads, including reloads folded into other instructions.
+    pub reloads: u64,
+    /// Number of copies between virtual registers.
+    pub copies: u64,
+}
+
+impl std::ops::AddAssign for SpillCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.spills += other.spills;
+        self.reloads += other.reloads;
+        self.copies += other.copies;
+    }
+}
+
+/// Type of a remark, which describes whether an optimization was applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RemarkType {
+    /// The optimization was not applied.
+    Missed,
+    /// The optimization was applied.
+    Passed,
+    /// Additional information about the code or about a (missed) optimization, e.g. the reason
+    /// why a loop was not vectorized.
+    Analysis,
+}
+
+impl RemarkType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RemarkType::Missed => "missed",
+            RemarkType::Passed => "passed",
+            RemarkType::Analysis => "analysis",
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct Remark {
     pub pass: String,
     pub name: String,
     pub function: Function,
     pub message: Vec<MessagePart>,
     pub hotness: Option<i32>,
+    pub stage: RemarkStage,
+    /// Passed and analysis remarks are only loaded if [`RemarkLoadOptions::passed`] or
+    /// [`RemarkLoadOptions::analysis`] is set.
+    pub remark_type: RemarkType,
+    /// Call described by the `Caller` and `Callee` arguments of the remark, if it has them.
+    pub call: Option<CallEdge>,
+    /// Spill counts described by the numeric arguments of register allocator remarks.
+    pub spills: Option<SpillCounts>,
+    /// Set if the callee or the message of the remark involves a panic path, e.g. a bounds check.
+    pub category: Option<RemarkCategory>,
+    /// Set if the kind of the remark is filtered, but it was loaded anyway for a report of the
+    /// website (see [`RemarkLoadOptions::spills`]). Filtered remarks are not shown in the remark
+    /// list and they are not exported.
+    #[serde(skip)]
+    pub filtered: bool,
+}
+
+impl Remark {
+    /// Message of the remark without source locations.
+    pub fn message_text(&self) -> String {
+        self.message
+            .iter()
+            .map(|part| match part {
+                MessagePart::String(message) => message.as_str(),
+                MessagePart::AnnotatedString { message, .. } => message.as_str(),
+            })
+            .collect()
+    }
 }
 
-#[derive(Default)]
+/// Writes a single line with the kind, location, function and message of the remark.
+pub(crate) fn write_remark<W: Write>(
+    writer: &mut W,
+    prefix: ColoredString,
+    remark: &Remark,
+) -> std::io::Result<()> {
+    let location = remark
+        .function
+        .location
+        .as_ref()
+        .map(|l| format!("{}:{}:{}", l.file, l.line, l.column))
+        .unwrap_or_else(|| "<unknown location>".to_string());
+    writeln!(
+        writer,
+        "{prefix} [{}] {location} ({}): {}",
+        remark.name.bold(),
+        remark.function.name,
+        remark.message_text()
+    )
+}
+
+#[derive(Default, Clone)]
 pub struct RemarkLoadOptions {
     /// Load remarks from external crates
     pub external: bool,
@@ -62,14 +221,30 @@ pub struct RemarkLoadOptions {
     pub filter_kind: Vec<String>,
     /// Root path of rustc toolchain sources
     pub rustc_source_root: Option<RustcSourceRoot>,
+    /// Load passed remarks (applied optimizations) in addition to missed remarks
+    pub passed: bool,
+    /// Load analysis remarks in addition to missed remarks
+    pub analysis: bool,
+    /// Load remarks without a location, if their function has a remark located in the source
+    /// directory (or if `external` is set). They are only useful for the function pages of the
+    /// website.
+    pub unlocated: bool,
+    /// Load spill remarks (see [`crate::spills`]) even if their kind is filtered. They are marked
+    /// with [`Remark::filtered`] then, so that they are only used by the spills report of the
+    /// website.
+    pub spills: bool,
 }
 
 pub fn load_remarks_from_file<P: AsRef<Path>>(
     path: P,
     options: &RemarkLoadOptions,
 ) -> anyhow::Result<Vec<Remark>> {
-    let path = path.as_ref();
+    let mut remarks = parse_remark_file(path.as_ref(), options)?;
+    filter_unlocated(&mut remarks, options);
+    Ok(remarks)
+}
 
+fn parse_remark_file(path: &Path, options: &RemarkLoadOptions) -> anyhow::Result<Vec<Remark>> {
     let file =
         File::open(path).with_context(|| format!("Cannot open remark file {}", path.display()))?;
     log::debug!("Parsing {}", path.display());
@@ -80,59 +255,83 @@ pub fn load_remarks_from_file<P: AsRef<Path>>(
     }
 
     let reader = BufReader::new(file);
+    let stage = RemarkStage::from_path(path);
 
-    let remarks = time_block_log_debug("Parsed remark file", || parse_remarks(reader, options));
+    let remarks = time_block_log_debug("Parsed remark file", || {
+        parse_remarks(reader, stage, options)
+    });
     Ok(remarks)
 }
 
-fn parse_remarks<R: std::io::Read>(reader: R, options: &RemarkLoadOptions) -> Vec<Remark> {
+fn parse_remarks<R: std::io::Read>(
+    reader: R,
+    stage: RemarkStage,
+    options: &RemarkLoadOptions,
+) -> Vec<Remark> {
     let mut remarks = vec![];
     for document in serde_yaml::Deserializer::from_reader(reader) {
         match parse::Remark::deserialize(document) {
             Ok(remark) => {
                 // TODO: optimize (intern)
-                match remark {
-                    parse::Remark::Missed(remark) => {
-                        let MissedRemark {
-                            pass,
-                            name,
-                            debug_loc,
-                            function,
-                            args,
-                            hotness,
-                        } = remark;
-
-                        if let Some(location) = debug_loc {
-                            if !options.external {
-                                if location.file.starts_with('/') {
-                                    continue;
-                                }
-                                if !options.source_dir.join(location.file.as_ref()).is_file() {
-                                    continue;
-                                }
-                            }
-                            if options
-                                .filter_kind
-                                .iter()
-                                .any(|filter| filter == name.as_ref())
-                            {
-                                continue;
-                            }
-
-                            let remark = Remark {
-                                pass: pass.to_string(),
-                                name: name.to_string(),
-                                function: Function {
-                                    name: demangle(&function),
-                                    location: Some(parse_debug_loc(options, location)),
-                                },
-                                message: construct_message(options, args),
-                                hotness,
-                            };
-                            remarks.push(remark);
+                let (remark_type, remark) = match remark {
+                    parse::Remark::Missed(remark) => (RemarkType::Missed, remark),
+                    parse::Remark::Passed(remark) if options.passed => (RemarkType::Passed, remark),
+                    parse::Remark::Analysis(remark)
+                    | parse::Remark::AnalysisFPCommute(remark)
+                    | parse::Remark::AnalysisAliasing(remark)
+                        if options.analysis =>
+                    {
+                        (RemarkType::Analysis, remark)
+                    }
+                    _ => continue,
+                };
+                let RemarkData {
+                    pass,
+                    name,
+                    debug_loc,
+                    function,
+                    args,
+                    hotness,
+                } = remark;
+
+                let filtered = is_kind_filtered(options, &name);
+                if filtered && !(options.spills && is_spill_remark(&name)) {
+                    continue;
+                }
+                let location = match debug_loc {
+                    Some(location) => {
+                        if is_external(options, &location) {
+                            continue;
                         }
+                        Some(parse_debug_loc(options, location))
                     }
-                    parse::Remark::Passed {} | parse::Remark::Analysis {} => {}
+                    None => None,
+                };
+
+                let function_name = demangle(&function);
+                let call = find_call(&args, &function_name);
+                let spills = find_spills(&args);
+                let message = construct_message(options, args);
+                let category = RemarkCategory::classify(call.as_ref(), &message);
+                let remark = Remark {
+                    pass: pass.to_string(),
+                    name: name.to_string(),
+                    call,
+                    spills,
+                    category,
+                    function: Function {
+                        name: function_name,
+                        mangled_name: Some(function.to_string()),
+                        location,
+                    },
+                    message,
+                    hotness,
+                    stage,
+                    remark_type,
+                    filtered,
+                };
+                if remark.function.location.is_some() || options.unlocated {
+                    remarks.push(remark);
                 }
             }
             Err(error) => {
@@ -140,9 +339,126 @@ fn parse_remarks<R: std::io::Read>(reader: R, options: &RemarkLoadOptions) -> Ve
             }
         }
     }
+
     remarks
 }
 
+/// Remarks without a location cannot be attributed to a source file, so they are only kept if
+/// their function also has a remark located in the source directory (or if `external` is set).
+/// Functions often have located remarks only in a different remark file (e.g. the optimization
+/// remarks of a codegen unit and its codegen remarks), so this has to be done after all files
+/// are loaded.
+fn filter_unlocated(remarks: &mut Vec<Remark>, options: &RemarkLoadOptions) {
+    if options.external
+        || remarks
+            .iter()
+            .all(|remark| remark.function.location.is_some())
+    {
+        return;
+    }
+    let located_functions: Set<String> = remarks
+        .iter()
+        .filter(|remark| remark.function.location.is_some())
+        .filter_map(|remark| remark.function.mangled_name.clone())
+        .collect();
+    remarks.retain(|remark| {
+        remark.function.location.is_some()
+            || remark
+                .function
+                .mangled_name
+                .as_ref()
+                .is_some_and(|name| located_functions.contains(name))
+    });
+}
+
+/// Finds the call described by the `Caller`, `Callee`, `Cost` and `Threshold` arguments of a
+/// remark. If there is no `Caller` argument, the function of the remark is the caller.
+fn find_call(arguments: &[RemarkArg], function: &str) -> Option<CallEdge> {
+    let mut caller = None;
+    let mut callee = None;
+    let mut cost = None;
+    let mut threshold = None;
+    let value_to_string = |value: &Value| match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    };
+    for arg in arguments {
+        match arg {
+            RemarkArg::Callee(arg) => callee = Some(demangle(&arg.callee)),
+            RemarkArg::Caller(arg) => caller = Some(demangle(&arg.caller)),
+            RemarkArg::Other(map) => {
+                if let Some(value) = map.get("Cost") {
+                    cost = value_to_string(value);
+                }
+                if let Some(value) = map.get("Threshold") {
+                    threshold = value_to_string(value);
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(CallEdge {
+        caller: caller.unwrap_or_else(|| function.to_string()),
+        callee: callee?,
+        cost,
+        threshold,
+    })
+}
+
+/// Sums the `NumSpills`, `NumReloads`, `NumVRCopies` and related arguments of a register allocator
+/// remark. Returns `None` if the remark has none of these arguments.
+fn find_spills(arguments: &[RemarkArg]) -> Option<SpillCounts> {
+    let mut counts = SpillCounts::default();
+    let mut found = false;
+    for arg in arguments {
+        let RemarkArg::Other(map) = arg else {
+            continue;
+        };
+        for (key, value) in map {
+            let count = match key.as_ref() {
+                "NumSpills" | "NumFoldedSpills" => &mut counts.spills,
+                "NumReloads" | "NumFoldedReloads" | "NumZeroCostFoldedReloads" => {
+                    &mut counts.reloads
+                }
+                "NumVRCopies" => &mut counts.copies,
+                _ => continue,
+            };
+            let value = match value {
+                Value::String(value) => value.parse().ok(),
+                Value::Number(value) => value.as_u64(),
+                _ => None,
+            };
+            if let Some(value) = value {
+                *count += value;
+                found = true;
+            }
+        }
+    }
+    found.then_some(counts)
+}
+
+/// Returns true if a remark with the given `name` and `location` should be ignored.
+fn is_filtered(options: &RemarkLoadOptions, name: &str, location: &parse::DebugLocation) -> bool {
+    is_external(options, location) || is_kind_filtered(options, name)
+}
+
+/// Returns true if the location is outside of the source directory and external remarks should
+/// be ignored.
+fn is_external(options: &RemarkLoadOptions, location: &parse::DebugLocation) -> bool {
+    !options.external
+        && (location.file.starts_with('/')
+            || !options.source_dir.join(location.file.as_ref()).is_file())
+}
+
+fn is_kind_filtered(options: &RemarkLoadOptions, name: &str) -> bool {
+    options.filter_kind.iter().any(|filter| filter == name)
+}
+
+fn is_spill_remark(name: &str) -> bool {
+    name == FUNCTION_SPILLS_REMARK || name == LOOP_SPILLS_REMARK
+}
+
 fn construct_message(opts: &RemarkLoadOptions, arguments: Vec<RemarkArg>) -> Vec<MessagePart> {
     let mut parts = vec![];
     let mut buffer = String::new();
@@ -254,7 +570,7 @@ pub fn load_remarks_from_dir<P: AsRef<Path>>(
     let remarks: Vec<(PathBuf, anyhow::Result<Vec<Remark>>)> = files
         .into_par_iter()
         .map(|file| {
-            let remarks = load_remarks_from_file(&file, &options);
+            let remarks = parse_remark_file(&file, &options);
             if let Some(callback) = callback {
                 callback.advance();
             }
@@ -262,7 +578,7 @@ pub fn load_remarks_from_dir<P: AsRef<Path>>(
         })
         .collect();
 
-    let remarks = remarks
+    let mut remarks = remarks
         .into_iter()
         .filter_map(|(path, result)| match result {
             Ok(remarks) => Some(remarks),
@@ -273,6 +589,7 @@ pub fn load_remarks_from_dir<P: AsRef<Path>>(
         })
         .flatten()
         .collect();
+    filter_unlocated(&mut remarks, &options);
 
     if let Some(callback) = callback {
         callback.finish();
@@ -322,15 +639,22 @@ fn demangle(function: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::remark::{parse_remarks, Remark, RemarkLoadOptions};
+    use crate::remark::{
+        filter_unlocated, parse_remarks, CallEdge, Remark, RemarkLoadOptions, RemarkStage,
+        RemarkType,
+    };
     use crate::RustcSourceRoot;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     struct Options {
         external: bool,
         filter_kind: Vec<String>,
         source_dir: PathBuf,
         rustc_source_root: Option<PathBuf>,
+        passed: bool,
+        analysis: bool,
+        unlocated: bool,
+        spills: bool,
     }
 
     impl Options {
@@ -348,6 +672,26 @@ mod tests {
             self.external = external;
             self
         }
+
+        fn passed(mut self) -> Self {
+            self.passed = true;
+            self
+        }
+
+        fn analysis(mut self) -> Self {
+            self.analysis = true;
+            self
+        }
+
+        fn unlocated(mut self) -> Self {
+            self.unlocated = true;
+            self
+        }
+
+        fn spills(mut self) -> Self {
+            self.spills = true;
+            self
+        }
     }
 
     impl Default for Options {
@@ -357,6 +701,10 @@ mod tests {
                 filter_kind: vec![],
                 source_dir: PathBuf::from("/tmp"),
                 rustc_source_root: None,
+                passed: false,
+                analysis: false,
+                unlocated: false,
+                spills: false,
             }
         }
     }
@@ -368,12 +716,20 @@ mod tests {
                 filter_kind,
                 source_dir,
                 rustc_source_root,
+                passed,
+                analysis,
+                unlocated,
+                spills,
             } = value;
             Self {
                 external,
                 source_dir,
                 filter_kind,
                 rustc_source_root: rustc_source_root.map(RustcSourceRoot),
+                passed,
+                analysis,
+                unlocated,
+                spills,
             }
         }
     }
@@ -399,6 +755,9 @@ Args:
                 name: "FastISelFailure",
                 function: Function {
                     name: "__rust_alloc",
+                    mangled_name: Some(
+                        "__rust_alloc",
+                    ),
                     location: Some(
                         Location {
                             file: "/std/src/sys_common/backtrace.rs",
@@ -413,6 +772,12 @@ Args:
                     ),
                 ],
                 hotness: None,
+                stage: PreLink,
+                remark_type: Missed,
+                call: None,
+                spills: None,
+                category: None,
+                filtered: false,
             },
         ]
         "###);
@@ -453,6 +818,9 @@ Args:
                 name: "NoDefinition",
                 function: Function {
                     name: "std::rt::lang_start",
+                    mangled_name: Some(
+                        "_ZN3std2rt10lang_start17h9096f6f84fb08eb2E",
+                    ),
                     location: Some(
                         Location {
                             file: "/foo/rust/rust/library/std/src/rt.rs",
@@ -478,12 +846,28 @@ Args:
                     ),
                 ],
                 hotness: None,
+                stage: PreLink,
+                remark_type: Missed,
+                call: Some(
+                    CallEdge {
+                        caller: "std::rt::lang_start",
+                        callee: "std::rt::lang_start_internal",
+                        cost: None,
+                        threshold: None,
+                    },
+                ),
+                spills: None,
+                category: None,
+                filtered: false,
             },
             Remark {
                 pass: "inline",
                 name: "NoDefinition",
                 function: Function {
                     name: "remarks::main",
+                    mangled_name: Some(
+                        "_ZN7remarks4main17hc92ae132ef1efa8eE",
+                    ),
                     location: Some(
                         Location {
                             file: "src/main.rs",
@@ -509,6 +893,19 @@ Args:
                     ),
                 ],
                 hotness: None,
+                stage: PreLink,
+                remark_type: Missed,
+                call: Some(
+                    CallEdge {
+                        caller: "remarks::main",
+                        callee: "std::io::stdio::_print",
+                        cost: None,
+                        threshold: None,
+                    },
+                ),
+                spills: None,
+                category: None,
+                filtered: false,
             },
         ]
         "###);
@@ -529,6 +926,49 @@ Args:
         insta::assert_debug_snapshot!(parse(input, Options::default()), @"[]");
     }
 
+    #[test]
+    fn parse_no_location_unlocated() {
+        let input = r#"--- !Missed
+Pass:            sdagisel
+Name:            FastISelFailure
+Function:        __rust_alloc
+Args:
+  - String:          FastISel missed call
+  - String:          ': '
+  - String:          '  %3 = tail call ptr @__rdl_alloc(i64 %0, i64 %1)'
+  - String:          ' (in function: __rust_alloc)'
+..."#;
+        insta::assert_debug_snapshot!(parse(input, Options::default().unlocated()), @r###"
+        [
+            Remark {
+                pass: "sdagisel",
+                name: "FastISelFailure",
+                function: Function {
+                    name: "__rust_alloc",
+                    mangled_name: Some(
+                        "__rust_alloc",
+                    ),
+                    location: None,
+                },
+                message: [
+                    String(
+                        "FastISel missed call:   %3 = tail call ptr @__rdl_alloc(i64 %0, i64 %1) (in function: __rust_alloc)",
+                    ),
+                ],
+                hotness: None,
+                stage: PreLink,
+                remark_type: Missed,
+                call: None,
+                spills: None,
+                category: None,
+                filtered: false,
+            },
+        ]
+        "###);
+        // Remarks without a location are only kept for functions located in the source directory
+        assert!(parse(input, Options::default().unlocated().external(false)).is_empty());
+    }
+
     #[test]
     fn parse_ignored_type() {
         let input = r#"--- !Passed
@@ -576,6 +1016,48 @@ Args:
   - Delta:           '-6'
 ..."#;
         assert!(parse(input, Options::default()).is_empty());
+
+        let remarks = parse(input, Options::default().passed());
+        assert_eq!(remarks.len(), 1);
+        assert_eq!(remarks[0].remark_type, RemarkType::Passed);
+        assert_eq!(remarks[0].name, "Inlined");
+
+        // The analysis remark does not have a location
+        let remarks = parse(input, Options::default().analysis().unlocated());
+        assert_eq!(remarks.len(), 1);
+        assert_eq!(remarks[0].remark_type, RemarkType::Analysis);
+        assert_eq!(remarks[0].name, "FunctionMISizeChange");
+    }
+
+    #[test]
+    fn parse_inlining_cost() {
+        let input = r#"--- !Missed
+Pass:            inline
+Name:            TooCostly
+DebugLoc:        { File: src/main.rs, Line: 10, Column: 5 }
+Function:        _ZN7remarks4main17hc92ae132ef1efa8eE
+Args:
+  - String:          ''''
+  - Callee:          _ZN7remarks3foo17h0a9e1c9f63e5a7a1E
+  - String:          ''' not inlined into '''
+  - Caller:          _ZN7remarks4main17hc92ae132ef1efa8eE
+  - String:          ''' because too costly to inline '
+  - String:          '(cost='
+  - Cost:            '345'
+  - String:          ', threshold='
+  - Threshold:       '250'
+  - String:          ')'
+..."#;
+        let remarks = parse(input, Options::default());
+        assert_eq!(
+            remarks[0].call,
+            Some(CallEdge {
+                caller: "remarks::main".to_string(),
+                callee: "remarks::foo".to_string(),
+                cost: Some("345".to_string()),
+                threshold: Some("250".to_string()),
+            })
+        );
     }
 
     #[test]
@@ -648,6 +1130,9 @@ Args:
                 name: "LoopSpillReloadCopies",
                 function: Function {
                     name: "std::io::append_to_string",
+                    mangled_name: Some(
+                        "_ZN3std2io16append_to_string17hcf3f6e91099a64a2E",
+                    ),
                     location: Some(
                         Location {
                             file: "/rustc/08d00b40aef2017fe6dba3ff7d6476efa0c10888/library/std/src/io/buffered/bufreader/buffer.rs",
@@ -664,11 +1149,45 @@ Args:
                 hotness: Some(
                     2,
                 ),
+                stage: PreLink,
+                remark_type: Missed,
+                call: None,
+                spills: Some(
+                    SpillCounts {
+                        spills: 0,
+                        reloads: 3,
+                        copies: 2,
+                    },
+                ),
+                category: None,
+                filtered: false,
             },
         ]
         "###);
     }
 
+    #[test]
+    fn parse_filtered_spills() {
+        let input = r#"--- !Missed
+Pass:            regalloc
+Name:            SpillReloadCopies
+Function:        main
+Args:
+  - NumVRCopies:     '1'
+  - String:          ' virtual registers copies '
+  - String:          generated in function
+..."#;
+        let options = || Options::default().filter("SpillReloadCopies").unlocated();
+        assert!(parse(input, options()).is_empty());
+
+        // Filtered spill remarks are loaded for the spills report, but they are marked
+        let remarks = parse(input, options().spills());
+        assert_eq!(remarks.len(), 1);
+        assert_eq!(remarks[0].remark_type, RemarkType::Missed);
+        assert!(remarks[0].filtered);
+        assert_eq!(remarks[0].spills.unwrap().copies, 1);
+    }
+
     #[test]
     fn parse_remap_rust_source() {
         let input = r#"--- !Missed
@@ -687,6 +1206,9 @@ Args:
                 name: "LoopSpillReloadCopies",
                 function: Function {
                     name: "std::io::append_to_string",
+                    mangled_name: Some(
+                        "_ZN3std2io16append_to_string17hcf3f6e91099a64a2E",
+                    ),
                     location: Some(
                         Location {
                             file: "/foo/bar/library/std/src/io/buffered/bufreader/buffer.rs",
@@ -697,12 +1219,37 @@ Args:
                 },
                 message: [],
                 hotness: None,
+                stage: PreLink,
+                remark_type: Missed,
+                call: None,
+                spills: None,
+                category: None,
+                filtered: false,
             },
         ]
         "###);
     }
 
+    #[test]
+    fn stage_from_path() {
+        let check = |path: &str| RemarkStage::from_path(Path::new(path));
+        assert_eq!(
+            check("foo.67ea4a01cbc73fb0-cgu.0.opt.opt.yaml"),
+            RemarkStage::PreLink
+        );
+        assert_eq!(check("/tmp/foo.0.lto.opt.yaml"), RemarkStage::Lto);
+        assert_eq!(
+            check("37v4yjwjhlguzgkm.codegen.opt.yaml"),
+            RemarkStage::Codegen
+        );
+        assert_eq!(check("foo.opt.yaml"), RemarkStage::Unknown);
+        assert_eq!(check("foo.yaml"), RemarkStage::Unknown);
+    }
+
     fn parse(input: &str, opts: Options) -> Vec<Remark> {
-        parse_remarks(input.as_bytes(), &opts.into())
+        let options: RemarkLoadOptions = opts.into();
+        let mut remarks = parse_remarks(input.as_bytes(), RemarkStage::PreLink, &options);
+        filter_unlocated(&mut remarks, &options);
+        remarks
     }
 }
@@ -51,9 +51,10 @@ pub enum RemarkArg<'a> {
     Other(BTreeMap<Cow<'a, str>, serde_yaml::Value>),
 }
 
+/// Fields that are shared by all remark types.
 #[derive(serde::Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
-pub struct MissedRemark<'a> {
+pub struct RemarkData<'a> {
     #[serde(borrow)]
     pub pass: Cow<'a, str>,
     #[serde(borrow)]
@@ -68,7 +69,15 @@ pub struct MissedRemark<'a> {
 #[derive(serde::Deserialize, Debug)]
 pub enum Remark<'a> {
     #[serde(borrow)]
-    Missed(MissedRemark<'a>),
-    Passed {},
-    Analysis {},
+    Missed(RemarkData<'a>),
+    #[serde(borrow)]
+    Passed(RemarkData<'a>),
+    #[serde(borrow)]
+    Analysis(RemarkData<'a>),
+    /// Analysis of a missed optimization that would require reordering floating-point operations.
+    #[serde(borrow)]
+    AnalysisFPCommute(RemarkData<'a>),
+    /// Analysis of a missed optimization that would require reordering memory operations.
+    #[serde(borrow)]
+    AnalysisAliasing(RemarkData<'a>),
 }
@@ -0,0 +1,273 @@
+//! Remarks that `rustc` prints as text diagnostics when it is invoked with `-Cremark`, but
+//! without `-Zremark-dir`. The `-Cremark` flag is stable, so this can be used as a fallback
+//! on stable toolchains, although the diagnostics contain less information than YAML remarks:
+//! - There is no remark name, so the name of the pass is used instead.
+//! - There is no function, so it is inferred from the message (if possible).
+//! - There is no hotness.
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+
+use crate::remark::{
+    demangle, is_filtered, parse, parse_debug_loc, CallEdge, Function, MessagePart, Remark,
+    RemarkCategory, RemarkLoadOptions, RemarkStage, RemarkType,
+};
+
+/// Returns the type of a remark with the given kind, as printed by `rustc`.
+fn remark_type(kind: &str) -> Option<RemarkType> {
+    match kind {
+        "missed" => Some(RemarkType::Missed),
+        "passed" => Some(RemarkType::Passed),
+        "analysis" | "floating-point" | "memory" => Some(RemarkType::Analysis),
+        _ => None,
+    }
+}
+
+/// Function name used when the function of a remark cannot be inferred from its message.
+pub const UNKNOWN_FUNCTION: &str = "<unknown>";
+
+static DIAGNOSTIC_REGEX: OnceLock<Regex> = OnceLock::new();
+static SYMBOL_REGEX: OnceLock<Regex> = OnceLock::new();
+static CALLER_REGEX: OnceLock<Regex> = OnceLock::new();
+static CALLEE_REGEX: OnceLock<Regex> = OnceLock::new();
+static COST_REGEX: OnceLock<Regex> = OnceLock::new();
+
+fn diagnostic_regex() -> &'static Regex {
+    DIAGNOSTIC_REGEX.get_or_init(|| {
+        Regex::new(r"(?s)^(.+):(\d+):(\d+) ([\w\-.]+) \((\w+)\): (.*)$")
+            .expect("Could not create regular expression")
+    })
+}
+
+/// Returns true if the diagnostic `message` is an optimization remark.
+pub fn is_remark_diagnostic(message: &str) -> bool {
+    diagnostic_regex().is_match(message)
+}
+
+/// Parses remarks from text diagnostics emitted by `rustc`.
+/// Diagnostics that are not optimization remarks are ignored, as well as passed and analysis
+/// remarks, unless they are enabled in `options`.
+pub fn load_remarks_from_diagnostics<'a, I: IntoIterator<Item = &'a str>>(
+    messages: I,
+    options: &RemarkLoadOptions,
+) -> Vec<Remark> {
+    messages
+        .into_iter()
+        .filter_map(|message| parse_text_remark(message, options))
+        .collect()
+}
+
+fn parse_text_remark(message: &str, options: &RemarkLoadOptions) -> Option<Remark> {
+    let captures = diagnostic_regex().captures(message)?;
+    let remark_type = remark_type(&captures[5])?;
+    let enabled = match remark_type {
+        RemarkType::Missed => true,
+        RemarkType::Passed => options.passed,
+        RemarkType::Analysis => options.analysis,
+    };
+    if !enabled {
+        return None;
+    }
+
+    // `rustc` prints absolute paths, but paths relative to the source directory are expected
+    let file = captures.get(1)?.as_str();
+    let file = match Path::new(file).strip_prefix(&options.source_dir) {
+        Ok(path) => Cow::Owned(path.to_str()?.replace('\\', "/")),
+        Err(_) => Cow::Borrowed(file),
+    };
+    let location = parse::DebugLocation {
+        file,
+        line: captures[2].parse().ok()?,
+        column: captures[3].parse().ok()?,
+    };
+    let pass = &captures[4];
+    // Remarks without a debug location are printed with a dummy location
+    if location.line == 0 || is_filtered(options, pass, &location) {
+        return None;
+    }
+
+    let text = &captures[6];
+    let mangled_name = CALLER_REGEX
+        .get_or_init(|| {
+            Regex::new(r" into '?((?:_ZN|_R)[\w$.]+)").expect("Could not create regular expression")
+        })
+        .captures(text)
+        .map(|captures| captures[1].to_string());
+    let function = mangled_name
+        .as_deref()
+        .map(demangle)
+        .unwrap_or_else(|| UNKNOWN_FUNCTION.to_string());
+    // Inlining remarks start with the callee, e.g. `'callee' not inlined into 'caller'`
+    let call = CALLEE_REGEX
+        .get_or_init(|| {
+            Regex::new(r"^'?((?:_ZN|_R)[\w$.]+)'? (?:not inlined|will not be inlined) into ")
+                .expect("Could not create regular expression")
+        })
+        .captures(text)
+        .filter(|_| mangled_name.is_some())
+        .map(|captures| {
+            let cost = COST_REGEX
+                .get_or_init(|| {
+                    Regex::new(r"\(cost=([\w\-]+)(?:, threshold=([\w\-]+))?\)")
+                        .expect("Could not create regular expression")
+                })
+                .captures(text);
+            let cost_part = |index| {
+                cost.as_ref()
+                    .and_then(|captures: &Captures| captures.get(index))
+                    .map(|part| part.as_str().to_string())
+            };
+            CallEdge {
+                caller: function.clone(),
+                callee: demangle(&captures[1]),
+                cost: cost_part(1),
+                threshold: cost_part(2),
+            }
+        });
+    let text = SYMBOL_REGEX
+        .get_or_init(|| {
+            Regex::new(r"'?\b((?:_ZN|_R)[\w$.]+)'?").expect("Could not create regular expression")
+        })
+        .replace_all(text, |captures: &Captures| demangle(&captures[1]));
+
+    let message = vec![MessagePart::String(text.into_owned())];
+    let category = RemarkCategory::classify(call.as_ref(), &message);
+
+    Some(Remark {
+        pass: pass.to_string(),
+        name: pass.to_string(),
+        function: Function {
+            name: function,
+            mangled_name,
+            location: Some(parse_debug_loc(options, location)),
+        },
+        message,
+        hotness: None,
+        stage: RemarkStage::Unknown,
+        remark_type,
+        call,
+        spills: None,
+        category,
+        filtered: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remark::text::{is_remark_diagnostic, load_remarks_from_diagnostics};
+    use crate::remark::{Remark, RemarkLoadOptions};
+    use std::path::PathBuf;
+
+    #[test]
+    fn parse_missed() {
+        let input = "src/main.rs:4:5 inline (missed): '_ZN5rtest3foo17h0a9e1c9f63e5a7a1E' not inlined into '_ZN5rtest4main17hc92ae132ef1efa8eE' because it should never be inlined (cost=never): noinline function attribute";
+        insta::assert_debug_snapshot!(parse(&[input]), @r###"
+        [
+            Remark {
+                pass: "inline",
+                name: "inline",
+                function: Function {
+                    name: "rtest::main",
+                    mangled_name: Some(
+                        "_ZN5rtest4main17hc92ae132ef1efa8eE",
+                    ),
+                    location: Some(
+                        Location {
+                            file: "src/main.rs",
+                            line: 4,
+                            column: 5,
+                        },
+                    ),
+                },
+                message: [
+                    String(
+                        "rtest::foo not inlined into rtest::main because it should never be inlined (cost=never): noinline function attribute",
+                    ),
+                ],
+                hotness: None,
+                stage: Unknown,
+                remark_type: Missed,
+                call: Some(
+                    CallEdge {
+                        caller: "rtest::main",
+                        callee: "rtest::foo",
+                        cost: Some(
+                            "never",
+                        ),
+                        threshold: None,
+                    },
+                ),
+                spills: None,
+                category: None,
+                filtered: false,
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn parse_absolute_path() {
+        let input = "/tmp/foo/src/main.rs:5:5 inline (missed): _RNvNtNtCsjrHSEGnQ3l9_3std2io5stdio6__print will not be inlined into _ZN5rtest4main17hd6d0f6564d30b456E because its definition is unavailable";
+        let remarks = load_remarks_from_diagnostics(
+            [input],
+            &RemarkLoadOptions {
+                source_dir: PathBuf::from("/tmp/foo"),
+                external: true,
+                ..Default::default()
+            },
+        );
+        let remark = &remarks[0];
+        assert_eq!(remark.function.name, "rtest::main");
+        assert_eq!(
+            remark.function.location.as_ref().unwrap().file,
+            "src/main.rs"
+        );
+        insta::assert_debug_snapshot!(remark.message, @r###"
+        [
+            String(
+                "std[e28293b1aa0f68bd]::io::stdio::_print will not be inlined into rtest::main because its definition is unavailable",
+            ),
+        ]
+        "###);
+    }
+
+    #[test]
+    fn parse_unknown_function() {
+        let input = "src/lib.rs:10:9 loop-vectorize (missed): loop not vectorized";
+        let remarks = parse(&[input]);
+        assert_eq!(remarks.len(), 1);
+        assert_eq!(remarks[0].function.name, "<unknown>");
+    }
+
+    #[test]
+    fn ignore_non_missed() {
+        let input = "src/main.rs:4:5 inline (success): '_ZN5rtest3foo17h0a9e1c9f63e5a7a1E' inlined into '_ZN5rtest4main17hc92ae132ef1efa8eE' with (cost=-15030, threshold=487)";
+        assert!(parse(&[input]).is_empty());
+    }
+
+    #[test]
+    fn ignore_unknown_location() {
+        let input = "<unknown file>:0:0 sdagisel (missed): FastISel missed call";
+        assert!(parse(&[input]).is_empty());
+    }
+
+    #[test]
+    fn detect_remark_diagnostic() {
+        assert!(is_remark_diagnostic(
+            "src/lib.rs:10:9 loop-vectorize (missed): loop not vectorized"
+        ));
+        assert!(!is_remark_diagnostic("unused variable: `x`"));
+    }
+
+    fn parse(messages: &[&str]) -> Vec<Remark> {
+        load_remarks_from_diagnostics(
+            messages.iter().copied(),
+            &RemarkLoadOptions {
+                external: true,
+                ..Default::default()
+            },
+        )
+    }
+}
@@ -11,17 +11,42 @@ use rayon::iter::IntoParallelIterator;
 use rayon::prelude::*;
 use rust_embed::RustEmbed;
 
-use crate::remark::{Line, Location, MessagePart, Remark};
+use crate::annotation::suppress::SuppressedRemark;
+use crate::bloat::find_generic_bloat;
+use crate::diff::{ChangedRemark, RemarkDiff};
+use crate::inlining::{find_near_misses, InliningGraph, DEFAULT_NEAR_MISS_MARGIN};
+use crate::knowledge::{KnowledgeBase, KnowledgeEntry};
+use crate::metrics::{compute_metrics, FunctionMetrics};
+use crate::remark::{Line, Location, MessagePart, Remark, RemarkType, SpillCounts};
+use crate::spills::aggregate_spills;
+use crate::stats::{RemarkCount, RemarkStats};
 use crate::utils::callback::LoadCallback;
 use crate::utils::data_structures::{Map, Set};
+use crate::vectorization::VectorizationReport;
 
 pub const INDEX_FILE_PATH: &str = "index.html";
+pub const DIFF_FILE_PATH: &str = "diff.html";
 const REMARK_LIST_FILE_PATH: &str = "remarks.html";
+const STATS_FILE_PATH: &str = "stats.html";
+const INLINING_FILE_PATH: &str = "inlining.html";
+const INLINING_DOT_FILE_PATH: &str = "inlining.dot";
+const NEAR_MISS_FILE_PATH: &str = "near-misses.html";
+const VECTORIZATION_FILE_PATH: &str = "vectorization.html";
+const METRICS_FILE_PATH: &str = "metrics.html";
+const GENERIC_BLOAT_FILE_PATH: &str = "generic-bloat.html";
+const SPILLS_FILE_PATH: &str = "spills.html";
+
+/// Maximum number of bars in a single chart of the statistics page.
+const STATS_CHART_LIMIT: usize = 20;
 
 /// Directory where sources will be stored.
 /// Relative to the output directory.
 const SRC_DIR_NAME: &str = "src";
 
+/// Directory where function pages will be stored.
+/// Relative to the output directory.
+const FUNCTION_DIR_NAME: &str = "fn";
+
 #[derive(RustEmbed)]
 #[folder = "templates/assets"]
 struct StaticAssets;
@@ -29,25 +54,37 @@ struct StaticAssets;
 #[derive(serde::Serialize)]
 struct RemarkIndexEntry<'a> {
     name: &'a str,
+    stage: &'static str,
     location: Option<String>,
     function: Cow<'a, str>,
     message: String,
     hotness: Option<i32>,
+    /// Reason why the remark was suppressed.
+    suppressed: Option<&'a str>,
+    /// Index of the explanation of the remark in the knowledge base.
+    explanation: Option<usize>,
+    /// Panic path category of the remark (e.g. `bounds-check`).
+    category: Option<&'static str>,
 }
 
 #[derive(serde::Serialize, PartialEq, Eq, Hash)]
 struct RemarkSourceEntry<'a> {
     name: &'a str,
-    function: &'a str,
+    /// Link to the page of the function.
+    function: String,
     line: Line,
     message: String,
     hotness: Option<i32>,
+    /// Index of the explanation of the remark in the knowledge base.
+    explanation: Option<usize>,
 }
 
 #[derive(Template)]
 #[template(path = "remark-list.jinja")]
 pub struct RemarkListTemplate {
     remarks_json: String,
+    /// HTML explanations of all knowledge base entries.
+    explanations_json: String,
 }
 
 #[derive(serde::Serialize)]
@@ -61,6 +98,176 @@ struct SourceFileLink<'a> {
 #[template(path = "index.jinja")]
 pub struct IndexTemplate<'a> {
     source_links: Vec<SourceFileLink<'a>>,
+    suppressed_count: usize,
+    show_suppressed: bool,
+}
+
+struct StatsBar {
+    /// HTML label of the bar.
+    label: String,
+    count: usize,
+    /// Width of the bar in percent, relative to the largest bar of the chart.
+    width: f64,
+}
+
+struct StatsChart {
+    title: &'static str,
+    bars: Vec<StatsBar>,
+    /// Number of entries that did not fit into the chart.
+    omitted: usize,
+}
+
+#[derive(Template)]
+#[template(path = "stats.jinja")]
+pub struct StatsTemplate {
+    total: usize,
+    without_hotness: usize,
+    charts: Vec<StatsChart>,
+}
+
+#[derive(serde::Serialize)]
+struct InliningNode<'a> {
+    name: &'a str,
+    /// Link to the page of the function, if it has one.
+    page: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct InliningEdgeEntry {
+    caller: usize,
+    callee: usize,
+    label: String,
+    hotness: Option<i32>,
+}
+
+#[derive(serde::Serialize)]
+struct InliningGraphEntry<'a> {
+    functions: Vec<InliningNode<'a>>,
+    edges: Vec<InliningEdgeEntry>,
+}
+
+#[derive(Template)]
+#[template(path = "inlining.jinja")]
+pub struct InliningTemplate {
+    graph_json: String,
+    function_count: usize,
+    edge_count: usize,
+    dot_file: &'static str,
+}
+
+/// Call that was not inlined, although its cost was close to the threshold.
+struct NearMissEntry {
+    /// Link to the call site.
+    location: Option<String>,
+    /// Link to the page of the caller.
+    caller: String,
+    /// Link to the page of the callee.
+    callee: String,
+    /// Link to the definition of the callee.
+    callee_location: Option<String>,
+    cost: i64,
+    threshold: i64,
+    margin_percent: f64,
+    hotness: Option<i32>,
+}
+
+#[derive(Template)]
+#[template(path = "near-misses.jinja")]
+pub struct NearMissTemplate {
+    near_misses: Vec<NearMissEntry>,
+    max_margin_percent: f64,
+}
+
+/// Vectorization remarks of a single location.
+struct VectorizationRow {
+    location: Option<String>,
+    /// Link to the page of the function.
+    function: String,
+    vectorized: bool,
+    /// Description of the vectorization, or of the failure.
+    status: String,
+    /// HTML descriptions of the reasons why the code was not vectorized.
+    reasons: Vec<String>,
+    /// Failure categories separated by `|`, used for filtering.
+    categories: String,
+    hotness: Option<i32>,
+}
+
+#[derive(Template)]
+#[template(path = "vectorization.jinja")]
+pub struct VectorizationTemplate {
+    rows: Vec<VectorizationRow>,
+    /// Failure categories with their counts.
+    categories: Vec<(&'static str, usize)>,
+    vectorized_count: usize,
+    has_passed: bool,
+    has_analysis: bool,
+}
+
+#[derive(serde::Serialize)]
+struct FunctionMetricsEntry {
+    /// Link to the page of the function.
+    function: String,
+    location: Option<String>,
+    stack_size: Option<u64>,
+    instruction_count: Option<u64>,
+}
+
+#[derive(Template)]
+#[template(path = "metrics.jinja")]
+pub struct MetricsTemplate {
+    metrics_json: String,
+    function_count: usize,
+}
+
+/// Instantiation of a generic function.
+struct InstantiationEntry {
+    /// Link to the page of the function.
+    function: String,
+    /// Symbol name, which distinguishes instantiations with the same demangled name.
+    symbol: Option<String>,
+    instruction_count: u64,
+}
+
+/// Generic function with all its instantiations.
+struct GenericFunctionEntry {
+    name: String,
+    instantiations: Vec<InstantiationEntry>,
+    total_instructions: u64,
+    average_instructions: u64,
+}
+
+#[derive(Template)]
+#[template(path = "generic-bloat.jinja")]
+pub struct GenericBloatTemplate {
+    functions: Vec<GenericFunctionEntry>,
+    /// Were any instruction counts loaded?
+    has_metrics: bool,
+}
+
+/// Spills of a single loop.
+struct LoopSpillsEntry {
+    location: Option<String>,
+    counts: SpillCounts,
+    hotness: Option<i32>,
+}
+
+/// Spills of a function, with the loops that contain them.
+struct FunctionSpillsEntry {
+    /// Link to the page of the function.
+    function: String,
+    counts: SpillCounts,
+    exact: bool,
+    loops: Vec<LoopSpillsEntry>,
+    hotness: Option<i32>,
+}
+
+#[derive(Template)]
+#[template(path = "spills.jinja")]
+pub struct SpillsTemplate {
+    functions: Vec<FunctionSpillsEntry>,
+    /// Are there any functions whose counts were summed from their loops?
+    has_inexact: bool,
 }
 
 #[derive(Template)]
@@ -68,11 +275,163 @@ pub struct IndexTemplate<'a> {
 pub struct SourceFileTemplate<'a> {
     path: &'a str,
     remarks: Set<RemarkSourceEntry<'a>>,
+    /// HTML explanations of the remarks in the file, keyed by their index in the knowledge base.
+    explanations: Map<usize, &'a str>,
     file_content: String,
 }
 
+struct FunctionRemarkEntry<'a> {
+    name: &'a str,
+    pass: &'a str,
+    stage: &'static str,
+    location: Option<String>,
+    message: String,
+    hotness: Option<i32>,
+}
+
+/// Calls between the function and another function that were not optimized (e.g. inlined).
+struct FunctionCallEntry {
+    /// Link to the other function.
+    function: String,
+    count: usize,
+    /// Remark kinds of the calls.
+    kinds: String,
+}
+
+#[derive(Template)]
+#[template(path = "function.jinja")]
+pub struct FunctionTemplate<'a> {
+    /// HTML-escaped name of the function.
+    name: String,
+    /// Maximum hotness of the remarks of the function.
+    hotness: Option<i32>,
+    remarks: Vec<FunctionRemarkEntry<'a>>,
+    callees: Vec<FunctionCallEntry>,
+    callers: Vec<FunctionCallEntry>,
+}
+
+/// Pages of all functions that contain remarks.
+struct FunctionPages<'a> {
+    ids: Map<&'a str, usize>,
+}
+
+impl<'a> FunctionPages<'a> {
+    fn new(remarks: &'a [Remark]) -> Self {
+        let mut names: Vec<&str> = remarks
+            .iter()
+            .map(|remark| remark.function.name.as_str())
+            .collect::<Set<_>>()
+            .into_iter()
+            .collect();
+        names.sort_unstable();
+        Self {
+            ids: names
+                .into_iter()
+                .enumerate()
+                .map(|(id, name)| (name, id))
+                .collect(),
+        }
+    }
+
+    fn page_path(&self, prefix: Option<&str>, id: usize) -> String {
+        let mut path = String::new();
+        if let Some(prefix) = prefix {
+            path.push_str(prefix);
+            path.push('/');
+        }
+        write!(path, "{FUNCTION_DIR_NAME}/{id}.html").unwrap();
+        path
+    }
+
+    /// Renders a link to the page of the function, or just its name if it does not have a page.
+    fn render_link(&self, buffer: &mut String, prefix: Option<&str>, function: &str) {
+        match self.ids.get(function) {
+            Some(&id) => {
+                buffer.push_str("<a href='");
+                buffer.push_str(&self.page_path(prefix, id));
+                buffer.push_str("'>");
+                encode_safe_to_string(function, buffer);
+                buffer.push_str("</a>");
+            }
+            None => {
+                encode_safe_to_string(function, buffer);
+            }
+        }
+    }
+
+    fn link(&self, prefix: Option<&str>, function: &str) -> String {
+        let mut buffer = String::new();
+        self.render_link(&mut buffer, prefix, function);
+        buffer
+    }
+}
+
+#[derive(serde::Serialize)]
+struct RemarkDiffEntry<'a> {
+    status: &'static str,
+    name: &'a str,
+    location: Option<String>,
+    function: Cow<'a, str>,
+    message: String,
+    hotness: Option<i32>,
+}
+
+#[derive(Template)]
+#[template(path = "diff.jinja")]
+pub struct DiffTemplate {
+    remarks_json: String,
+    new_count: usize,
+    fixed_count: usize,
+    changed_count: usize,
+}
+
+fn create_index_entry<'a>(
+    remark: &'a Remark,
+    functions: &FunctionPages,
+    knowledge: &KnowledgeBase,
+    suppressed: Option<&'a str>,
+) -> RemarkIndexEntry<'a> {
+    let Remark {
+        pass: _,
+        name,
+        function,
+        message,
+        hotness,
+        stage,
+        remark_type: _,
+        call: _,
+        spills: _,
+        category,
+        filtered: _,
+    } = remark;
+
+    RemarkIndexEntry {
+        name,
+        stage: stage.as_str(),
+        location: function.location.as_ref().map(|location| {
+            let mut buffer = String::new();
+            render_remark_link(&mut buffer, location, Some(SRC_DIR_NAME), None);
+            buffer
+        }),
+        function: Cow::Owned(functions.link(None, &function.name)),
+        message: format_message(message, Some(SRC_DIR_NAME)),
+        hotness: *hotness,
+        suppressed,
+        explanation: knowledge.position(remark),
+        category: category.map(|category| category.as_str()),
+    }
+}
+
+/// Renders a website with the remarks.
+/// Suppressed remarks are only counted on the index page, unless `show_suppressed` is used, in
+/// which case they are also shown greyed out in the remark list.
+/// Passed and analysis remarks are only used by the vectorization report.
+/// Remarks are shown with their explanation from the `knowledge` base, if there is one.
 pub fn render_remarks(
     remarks: Vec<Remark>,
+    suppressed: &[SuppressedRemark],
+    show_suppressed: bool,
+    knowledge: &KnowledgeBase,
     source_dir: &Path,
     output_dir: &Path,
     callback: Option<&(dyn LoadCallback + Sync)>,
@@ -80,52 +439,43 @@ pub fn render_remarks(
     let _ = std::fs::remove_dir_all(output_dir);
     std::fs::create_dir_all(output_dir).context("Cannot create output directory")?;
 
-    // Copy all static assets to the output directory
-    for asset_path in StaticAssets::iter() {
-        let data = StaticAssets::get(&asset_path).unwrap().data;
-        let path = output_dir.join("assets").join(asset_path.as_ref());
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent).context("Cannot create output asset directory")?;
-        }
-        std::fs::write(path, data).context("Cannot copy asset file to output directory")?;
-    }
+    copy_assets(output_dir)?;
+
+    // Filtered remarks are only loaded for the spills report
+    let (filtered_remarks, remarks): (Vec<Remark>, Vec<Remark>) =
+        remarks.into_iter().partition(|remark| remark.filtered);
+    let (remarks, other_remarks): (Vec<Remark>, Vec<Remark>) = remarks
+        .into_iter()
+        .partition(|remark| remark.remark_type == RemarkType::Missed);
 
     let mut file_to_remarks: Map<&str, Set<RemarkSourceEntry>> = Map::default();
+    let functions = FunctionPages::new(&remarks);
 
     // Create remark list page
-    let remark_entries = remarks
+    let mut remark_entries = remarks
         .iter()
         .map(|r| {
             let Remark {
-                pass: _,
                 name,
                 function,
                 message,
                 hotness,
+                ..
             } = r;
 
-            let entry = RemarkIndexEntry {
-                name,
-                location: function.location.as_ref().map(|location| {
-                    let mut buffer = String::new();
-                    render_remark_link(&mut buffer, location, Some(SRC_DIR_NAME), None);
-                    buffer
-                }),
-                function: encode_safe(&function.name),
-                message: format_message(message, Some(SRC_DIR_NAME)),
-                hotness: *hotness,
-            };
+            let entry = create_index_entry(r, &functions, knowledge, None);
             if let Some(ref location) = function.location {
                 file_to_remarks
                     .entry(&location.file)
                     .or_default()
                     .insert(RemarkSourceEntry {
                         name,
-                        function: &function.name,
+                        function: functions.link(Some(".."), &function.name),
                         line: location.line,
                         // Inside the file, the link should be relative to the src directory
                         message: format_message(message, None),
                         hotness: *hotness,
+                        explanation: entry.explanation,
                     });
             }
             // We also need to create file mappings for all referenced files, not just for files
@@ -138,13 +488,32 @@ pub fn render_remarks(
             entry
         })
         .collect::<Vec<_>>();
+    if show_suppressed {
+        remark_entries.extend(suppressed.iter().map(|suppressed| {
+            create_index_entry(
+                &suppressed.remark,
+                &functions,
+                knowledge,
+                Some(&suppressed.reason),
+            )
+        }));
+    }
 
+    let explanations: Vec<String> = knowledge.entries().iter().map(render_explanation).collect();
     let serialized_remarks = serde_json::to_string(&remark_entries)?;
     let remark_list_page = RemarkListTemplate {
         remarks_json: serialized_remarks,
+        explanations_json: serde_json::to_string(&explanations)?,
     };
     render_to_file(&remark_list_page, &output_dir.join(REMARK_LIST_FILE_PATH))?;
 
+    // Source files referenced by the vectorization report
+    for remark in &other_remarks {
+        if let Some(location) = &remark.function.location {
+            file_to_remarks.entry(&location.file).or_default();
+        }
+    }
+
     let mut source_links: Vec<SourceFileLink> = file_to_remarks
         .iter()
         .filter(|(_, remarks)| !remarks.is_empty())
@@ -162,9 +531,40 @@ pub fn render_remarks(
     // Sort by relative files first, then in descending order by remark count
     source_links.sort_by_key(|link| (link.name.starts_with('/'), -(link.remark_count as i64)));
 
-    let index_page = IndexTemplate { source_links };
+    let index_page = IndexTemplate {
+        source_links,
+        suppressed_count: suppressed.len(),
+        show_suppressed,
+    };
     render_to_file(&index_page, &output_dir.join(INDEX_FILE_PATH))?;
 
+    let stats_page = create_stats_page(&RemarkStats::compute(&remarks), &functions);
+    render_to_file(&stats_page, &output_dir.join(STATS_FILE_PATH))?;
+
+    render_inlining_graph(&remarks, &functions, output_dir)?;
+
+    let near_miss_page = create_near_miss_page(&remarks, &functions);
+    render_to_file(&near_miss_page, &output_dir.join(NEAR_MISS_FILE_PATH))?;
+
+    let vectorization_page = create_vectorization_page(&remarks, &other_remarks, &functions);
+    render_to_file(
+        &vectorization_page,
+        &output_dir.join(VECTORIZATION_FILE_PATH),
+    )?;
+
+    let spills_page = create_spills_page(&remarks, &filtered_remarks, &functions);
+    render_to_file(&spills_page, &output_dir.join(SPILLS_FILE_PATH))?;
+
+    let metrics = compute_metrics(&other_remarks);
+    let metrics_page = create_metrics_page(&metrics, &functions)?;
+    render_to_file(&metrics_page, &output_dir.join(METRICS_FILE_PATH))?;
+
+    let generic_bloat_page = create_generic_bloat_page(&metrics, &functions);
+    render_to_file(
+        &generic_bloat_page,
+        &output_dir.join(GENERIC_BLOAT_FILE_PATH),
+    )?;
+
     if let Some(callback) = callback {
         callback.start(file_to_remarks.len() as u64);
     }
@@ -185,9 +585,15 @@ pub fn render_remarks(
             let mut buffer = String::new();
             path_to_relative_url(&mut buffer, Some(SRC_DIR_NAME), source_file);
             let output_path = output_dir.join(buffer);
+            let explanations = remarks
+                .iter()
+                .filter_map(|remark| remark.explanation)
+                .map(|index| (index, explanations[index].as_str()))
+                .collect();
             let source_file_page = SourceFileTemplate {
                 path: source_file,
                 remarks,
+                explanations,
                 file_content,
             };
             render_to_file(&source_file_page, Path::new(&output_path))
@@ -201,6 +607,8 @@ pub fn render_remarks(
         log::warn!("Failed to write {failed} source file(s)");
     }
 
+    render_function_pages(&remarks, &functions, output_dir)?;
+
     if let Some(callback) = callback {
         callback.finish();
     }
@@ -208,6 +616,591 @@ pub fn render_remarks(
     Ok(())
 }
 
+/// Renders an interactive page with the graph of inlining decisions and exports the graph in the
+/// DOT format, so that it can be downloaded from the page.
+fn render_inlining_graph(
+    remarks: &[Remark],
+    functions: &FunctionPages,
+    output_dir: &Path,
+) -> anyhow::Result<()> {
+    let graph = InliningGraph::from_remarks(remarks);
+
+    let dot_path = output_dir.join(INLINING_DOT_FILE_PATH);
+    let file =
+        File::create(&dot_path).with_context(|| format!("Cannot create {}", dot_path.display()))?;
+    graph
+        .write_dot(BufWriter::new(file))
+        .with_context(|| format!("Cannot write {}", dot_path.display()))?;
+
+    let entry = InliningGraphEntry {
+        functions: graph
+            .functions
+            .iter()
+            .map(|name| InliningNode {
+                name,
+                page: functions
+                    .ids
+                    .get(name.as_str())
+                    .map(|&id| functions.page_path(None, id)),
+            })
+            .collect(),
+        edges: graph
+            .edges
+            .iter()
+            .map(|edge| InliningEdgeEntry {
+                caller: edge.caller,
+                callee: edge.callee,
+                label: edge.label(),
+                hotness: edge.hotness,
+            })
+            .collect(),
+    };
+    let page = InliningTemplate {
+        graph_json: serde_json::to_string(&entry)?,
+        function_count: graph.functions.len(),
+        edge_count: graph.edges.len(),
+        dot_file: INLINING_DOT_FILE_PATH,
+    };
+    render_to_file(&page, &output_dir.join(INLINING_FILE_PATH))
+}
+
+fn create_near_miss_page(remarks: &[Remark], functions: &FunctionPages) -> NearMissTemplate {
+    let source_link = |location: &Location| {
+        let mut buffer = String::new();
+        render_remark_link(&mut buffer, location, Some(SRC_DIR_NAME), None);
+        buffer
+    };
+    let near_misses = find_near_misses(remarks, DEFAULT_NEAR_MISS_MARGIN)
+        .into_iter()
+        .map(|near_miss| NearMissEntry {
+            location: near_miss.remark.function.location.as_ref().map(source_link),
+            caller: functions.link(None, &near_miss.call.caller),
+            callee: functions.link(None, &near_miss.call.callee),
+            callee_location: near_miss.callee_location().map(source_link),
+            cost: near_miss.cost,
+            threshold: near_miss.threshold,
+            margin_percent: near_miss.margin() * 100.0,
+            hotness: near_miss.remark.hotness,
+        })
+        .collect();
+    NearMissTemplate {
+        near_misses,
+        max_margin_percent: DEFAULT_NEAR_MISS_MARGIN * 100.0,
+    }
+}
+
+fn create_vectorization_page(
+    remarks: &[Remark],
+    other_remarks: &[Remark],
+    functions: &FunctionPages,
+) -> VectorizationTemplate {
+    let has_passed = other_remarks
+        .iter()
+        .any(|remark| remark.remark_type == RemarkType::Passed);
+    let has_analysis = other_remarks
+        .iter()
+        .any(|remark| remark.remark_type == RemarkType::Analysis);
+    let report = VectorizationReport::from_remarks(remarks.iter().chain(other_remarks));
+
+    let rows = report
+        .entries
+        .iter()
+        .map(|entry| {
+            let status = if entry.is_vectorized() {
+                let details: Vec<String> = entry
+                    .vectorizations
+                    .iter()
+                    .map(|vectorization| {
+                        let mut parts = vec![];
+                        if let Some(factor) = &vectorization.factor {
+                            parts.push(format!("factor {factor}"));
+                        }
+                        if let Some(count) = &vectorization.interleave_count {
+                            parts.push(format!("interleave count {count}"));
+                        }
+                        if parts.is_empty() {
+                            vectorization.remark.name.clone()
+                        } else {
+                            parts.join(", ")
+                        }
+                    })
+                    .collect();
+                format!("Vectorized ({})", dedup_in_order(details).join("; "))
+            } else {
+                "Not vectorized".to_string()
+            };
+            let mut reasons: Vec<String> = entry
+                .failures
+                .iter()
+                .map(|failure| {
+                    format!(
+                        "<b>{}</b>: {}",
+                        failure.category.description(),
+                        format_message(&failure.remark.message, Some(SRC_DIR_NAME))
+                    )
+                })
+                .collect();
+            if reasons.is_empty() && !entry.is_vectorized() {
+                reasons.extend(
+                    entry
+                        .missed
+                        .iter()
+                        .map(|remark| format_message(&remark.message, Some(SRC_DIR_NAME))),
+                );
+            }
+            let mut categories: Vec<&str> = entry
+                .failures
+                .iter()
+                .map(|failure| failure.category.description())
+                .collect();
+            categories.sort_unstable();
+            categories.dedup();
+            let reasons = dedup_in_order(reasons);
+            VectorizationRow {
+                location: entry.location.map(|location| {
+                    let mut buffer = String::new();
+                    render_remark_link(&mut buffer, location, Some(SRC_DIR_NAME), None);
+                    buffer
+                }),
+                function: functions.link(None, entry.function),
+                vectorized: entry.is_vectorized(),
+                status,
+                reasons,
+                categories: categories.join("|"),
+                hotness: entry.hotness,
+            }
+        })
+        .collect::<Vec<_>>();
+    VectorizationTemplate {
+        vectorized_count: rows.iter().filter(|row| row.vectorized).count(),
+        rows,
+        categories: report
+            .categories
+            .iter()
+            .map(|(category, count)| (category.description(), *count))
+            .collect(),
+        has_passed,
+        has_analysis,
+    }
+}
+
+fn create_metrics_page(
+    metrics: &[FunctionMetrics],
+    functions: &FunctionPages,
+) -> anyhow::Result<MetricsTemplate> {
+    let entries: Vec<FunctionMetricsEntry> = metrics
+        .iter()
+        .map(|metrics| FunctionMetricsEntry {
+            function: functions.link(None, &metrics.function.name),
+            location: metrics.function.location.as_ref().map(|location| {
+                let mut buffer = String::new();
+                render_remark_link(&mut buffer, location, Some(SRC_DIR_NAME), None);
+                buffer
+            }),
+            stack_size: metrics.stack_size,
+            instruction_count: metrics.instruction_count,
+        })
+        .collect();
+    Ok(MetricsTemplate {
+        metrics_json: serde_json::to_string(&entries)?,
+        function_count: entries.len(),
+    })
+}
+
+fn create_generic_bloat_page(
+    metrics: &[FunctionMetrics],
+    functions: &FunctionPages,
+) -> GenericBloatTemplate {
+    let generic_functions = find_generic_bloat(metrics)
+        .into_iter()
+        .map(|function| GenericFunctionEntry {
+            name: html_escape::encode_safe(&function.name).to_string(),
+            instantiations: function
+                .instantiations
+                .iter()
+                .map(|instantiation| InstantiationEntry {
+                    function: functions.link(None, &instantiation.function.name),
+                    symbol: instantiation
+                        .function
+                        .mangled_name
+                        .as_deref()
+                        .map(|name| html_escape::encode_safe(name).to_string()),
+                    instruction_count: instantiation.instruction_count.unwrap_or_default(),
+                })
+                .collect(),
+            total_instructions: function.total_instructions,
+            average_instructions: function.total_instructions
+                / function.instantiations.len() as u64,
+        })
+        .collect();
+    GenericBloatTemplate {
+        functions: generic_functions,
+        has_metrics: metrics
+            .iter()
+            .any(|metrics| metrics.instruction_count.is_some()),
+    }
+}
+
+fn create_spills_page(
+    remarks: &[Remark],
+    filtered_remarks: &[Remark],
+    functions: &FunctionPages,
+) -> SpillsTemplate {
+    let source_link = |location: &Location| {
+        let mut buffer = String::new();
+        render_remark_link(&mut buffer, location, Some(SRC_DIR_NAME), None);
+        buffer
+    };
+    let spills: Vec<FunctionSpillsEntry> = aggregate_spills(remarks.iter().chain(filtered_remarks))
+        .into_iter()
+        .map(|function| FunctionSpillsEntry {
+            function: functions.link(None, &function.function.name),
+            counts: function.counts,
+            exact: function.exact,
+            loops: function
+                .loops
+                .iter()
+                .map(|spills| LoopSpillsEntry {
+                    location: spills.remark.function.location.as_ref().map(source_link),
+                    counts: spills.counts,
+                    hotness: spills.remark.hotness,
+                })
+                .collect(),
+            hotness: function.hotness,
+        })
+        .collect();
+    SpillsTemplate {
+        has_inexact: spills.iter().any(|function| !function.exact),
+        functions: spills,
+    }
+}
+
+/// Removes duplicated items (e.g. from remarks that were emitted multiple times), while keeping
+/// the order of the first occurrences.
+fn dedup_in_order(items: Vec<String>) -> Vec<String> {
+    let mut seen = Set::default();
+    items
+        .into_iter()
+        .filter(|item| seen.insert(item.clone()))
+        .collect()
+}
+
+/// Remark kinds of calls from (or to) a function, grouped by the other function of the call.
+type CallMap<'a> = Map<&'a str, Map<&'a str, Vec<&'a str>>>;
+
+/// Renders a page for each function, with all its remarks and with calls from and to the function
+/// that have not been inlined.
+fn render_function_pages(
+    remarks: &[Remark],
+    functions: &FunctionPages,
+    output_dir: &Path,
+) -> anyhow::Result<()> {
+    let mut function_remarks: Map<&str, Vec<&Remark>> = Map::default();
+    let mut callees: CallMap = Map::default();
+    let mut callers: CallMap = Map::default();
+    for remark in remarks {
+        function_remarks
+            .entry(&remark.function.name)
+            .or_default()
+            .push(remark);
+        if let Some(call) = &remark.call {
+            callees
+                .entry(&call.caller)
+                .or_default()
+                .entry(&call.callee)
+                .or_default()
+                .push(&remark.name);
+            callers
+                .entry(&call.callee)
+                .or_default()
+                .entry(&call.caller)
+                .or_default()
+                .push(&remark.name);
+        }
+    }
+
+    let src_prefix = format!("..{MAIN_SEPARATOR}{SRC_DIR_NAME}");
+    function_remarks
+        .into_par_iter()
+        .map(|(name, mut remarks)| -> anyhow::Result<()> {
+            // Remarks without a location are shown last
+            remarks.sort_by_key(|remark| {
+                let location = remark
+                    .function
+                    .location
+                    .as_ref()
+                    .map(|location| (&location.file, location.line, location.column));
+                (
+                    location.is_none(),
+                    location,
+                    -(remark.hotness.unwrap_or(0) as i64),
+                )
+            });
+            let page = FunctionTemplate {
+                name: encode_safe(name).into_owned(),
+                hotness: remarks.iter().filter_map(|remark| remark.hotness).max(),
+                remarks: remarks
+                    .iter()
+                    .map(|remark| FunctionRemarkEntry {
+                        name: &remark.name,
+                        pass: &remark.pass,
+                        stage: remark.stage.as_str(),
+                        location: remark.function.location.as_ref().map(|location| {
+                            let mut buffer = String::new();
+                            render_remark_link(&mut buffer, location, Some(&src_prefix), None);
+                            buffer
+                        }),
+                        message: format_message(&remark.message, Some(&src_prefix)),
+                        hotness: remark.hotness,
+                    })
+                    .collect(),
+                callees: create_call_entries(callees.get(name), functions),
+                callers: create_call_entries(callers.get(name), functions),
+            };
+            let path = functions.page_path(None, functions.ids[name]);
+            render_to_file(&page, &output_dir.join(path))
+                .with_context(|| format!("Failed to render page of function {name}"))
+        })
+        .collect::<anyhow::Result<()>>()
+}
+
+fn create_call_entries(
+    calls: Option<&Map<&str, Vec<&str>>>,
+    functions: &FunctionPages,
+) -> Vec<FunctionCallEntry> {
+    let Some(calls) = calls else {
+        return vec![];
+    };
+    let mut entries: Vec<(&str, FunctionCallEntry)> = calls
+        .iter()
+        .map(|(function, kinds)| {
+            let mut unique_kinds: Vec<&str> = kinds.clone();
+            unique_kinds.sort_unstable();
+            unique_kinds.dedup();
+            (
+                *function,
+                FunctionCallEntry {
+                    function: functions.link(Some(".."), function),
+                    count: kinds.len(),
+                    kinds: unique_kinds.join(", "),
+                },
+            )
+        })
+        .collect();
+    entries.sort_by(|(name_a, a), (name_b, b)| b.count.cmp(&a.count).then(name_a.cmp(name_b)));
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+fn create_stats_page(stats: &RemarkStats, functions: &FunctionPages) -> StatsTemplate {
+    let escaped = |count: &RemarkCount| encode_safe(&count.key).into_owned();
+    let function_link = |count: &RemarkCount| functions.link(None, &count.key);
+    let file_link = |count: &RemarkCount| {
+        let mut link = String::from("<a href='");
+        path_to_relative_url(&mut link, Some(SRC_DIR_NAME), &count.key);
+        link.push_str("'>");
+        encode_safe_to_string(&count.key, &mut link);
+        link.push_str("</a>");
+        link
+    };
+
+    let mut charts = vec![
+        create_chart(
+            "Remarks by kind",
+            &stats.by_name,
+            STATS_CHART_LIMIT,
+            escaped,
+        ),
+        create_chart(
+            "Remarks by pass",
+            &stats.by_pass,
+            STATS_CHART_LIMIT,
+            escaped,
+        ),
+        create_chart(
+            "Remarks by crate",
+            &stats.by_crate,
+            STATS_CHART_LIMIT,
+            escaped,
+        ),
+        create_chart(
+            "Files with the most remarks",
+            &stats.by_file,
+            STATS_CHART_LIMIT,
+            file_link,
+        ),
+        create_chart(
+            "Functions with the most remarks",
+            &stats.by_function,
+            STATS_CHART_LIMIT,
+            function_link,
+        ),
+    ];
+    if !stats.by_function_hotness.is_empty() {
+        charts.push(create_chart(
+            "Functions with the hottest remarks (sum of hotness)",
+            &stats.by_function_hotness,
+            STATS_CHART_LIMIT,
+            function_link,
+        ));
+    }
+    if !stats.hotness_histogram.is_empty() {
+        let buckets: Vec<RemarkCount> = stats
+            .hotness_histogram
+            .iter()
+            .map(|bucket| RemarkCount {
+                key: if bucket.min == bucket.max {
+                    bucket.min.to_string()
+                } else {
+                    format!("{}–{}", bucket.min, bucket.max)
+                },
+                count: bucket.count,
+            })
+            .collect();
+        // The histogram is ordered by hotness, so it is not truncated
+        charts.push(create_chart(
+            "Hotness histogram",
+            &buckets,
+            usize::MAX,
+            escaped,
+        ));
+    }
+
+    StatsTemplate {
+        total: stats.total,
+        without_hotness: stats.without_hotness,
+        charts,
+    }
+}
+
+fn create_chart(
+    title: &'static str,
+    counts: &[RemarkCount],
+    limit: usize,
+    label: impl Fn(&RemarkCount) -> String,
+) -> StatsChart {
+    let max = counts.iter().map(|count| count.count).max().unwrap_or(0);
+    StatsChart {
+        title,
+        bars: counts
+            .iter()
+            .take(limit)
+            .map(|count| StatsBar {
+                label: label(count),
+                count: count.count,
+                width: if max == 0 {
+                    0.0
+                } else {
+                    count.count as f64 / max as f64 * 100.0
+                },
+            })
+            .collect(),
+        omitted: counts.len().saturating_sub(limit),
+    }
+}
+
+/// Renders a page with the differences between remarks of two builds.
+/// The page does not contain source files, so locations are rendered without links.
+pub fn render_diff(diff: &RemarkDiff, output_dir: &Path) -> anyhow::Result<()> {
+    let _ = std::fs::remove_dir_all(output_dir);
+    std::fs::create_dir_all(output_dir).context("Cannot create output directory")?;
+    copy_assets(output_dir)?;
+
+    let mut entries = vec![];
+    for remark in &diff.new {
+        entries.push(diff_entry(
+            "new",
+            remark,
+            encode_safe(&remark.message_text()).into_owned(),
+        ));
+    }
+    for remark in &diff.fixed {
+        entries.push(diff_entry(
+            "fixed",
+            remark,
+            encode_safe(&remark.message_text()).into_owned(),
+        ));
+    }
+    for ChangedRemark { old, new } in &diff.changed {
+        let message = format!(
+            "{}<br><i>was: {}</i>",
+            encode_safe(&new.message_text()),
+            encode_safe(&old.message_text())
+        );
+        entries.push(diff_entry("changed", new, message));
+    }
+
+    let page = DiffTemplate {
+        remarks_json: serde_json::to_string(&entries)?,
+        new_count: diff.new.len(),
+        fixed_count: diff.fixed.len(),
+        changed_count: diff.changed.len(),
+    };
+    render_to_file(&page, &output_dir.join(DIFF_FILE_PATH))
+}
+
+fn diff_entry<'a>(
+    status: &'static str,
+    remark: &'a Remark,
+    message: String,
+) -> RemarkDiffEntry<'a> {
+    RemarkDiffEntry {
+        status,
+        name: &remark.name,
+        location: remark
+            .function
+            .location
+            .as_ref()
+            .map(|l| encode_safe(&format!("{}:{}:{}", l.file, l.line, l.column)).into_owned()),
+        function: encode_safe(&remark.function.name),
+        message,
+        hotness: remark.hotness,
+    }
+}
+
+/// Copies all static assets to the output directory.
+fn copy_assets(output_dir: &Path) -> anyhow::Result<()> {
+    for asset_path in StaticAssets::iter() {
+        let data = StaticAssets::get(&asset_path).unwrap().data;
+        let path = output_dir.join("assets").join(asset_path.as_ref());
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Cannot create output asset directory")?;
+        }
+        std::fs::write(path, data).context("Cannot copy asset file to output directory")?;
+    }
+    Ok(())
+}
+
+/// Renders the explanation of a knowledge base entry, followed by a list of its suggestions.
+fn render_explanation(entry: &KnowledgeEntry) -> String {
+    let mut buffer = String::new();
+    buffer.push_str("<div>");
+    render_inline_code(&mut buffer, &entry.explanation);
+    buffer.push_str("</div>");
+    if !entry.suggestions.is_empty() {
+        buffer.push_str("<ul>");
+        for suggestion in &entry.suggestions {
+            buffer.push_str("<li>");
+            render_inline_code(&mut buffer, suggestion);
+            buffer.push_str("</li>");
+        }
+        buffer.push_str("</ul>");
+    }
+    buffer
+}
+
+/// Escapes the text and renders parts of it that are enclosed in backticks as code.
+fn render_inline_code(buffer: &mut String, text: &str) {
+    for (index, part) in text.split('`').enumerate() {
+        if index % 2 == 1 {
+            buffer.push_str("<code>");
+            encode_safe_to_string(part, buffer);
+            buffer.push_str("</code>");
+        } else {
+            encode_safe_to_string(part, buffer);
+        }
+    }
+}
+
 fn format_message(parts: &[MessagePart], prefix: Option<&str>) -> String {
     let mut buffer = String::with_capacity(32);
     for part in parts {
@@ -277,7 +1270,7 @@ fn path_to_relative_url(buffer: &mut String, prefix: Option<&str>, path: &str) {
     buffer.push_str(".html");
 }
 
-fn resolve_path<'a>(root_dir: &Path, path: &'a Path) -> Cow<'a, Path> {
+pub fn resolve_path<'a>(root_dir: &Path, path: &'a Path) -> Cow<'a, Path> {
     if path.is_absolute() {
         path.into()
     } else {
@@ -0,0 +1,132 @@
+//! Report of register pressure, which aggregates the spill and reload counts of register allocator
+//! remarks per function and per loop.
+use crate::remark::{Function, Remark, SpillCounts};
+use crate::utils::data_structures::Map;
+
+/// Remark that describes the spills of a whole function.
+pub const FUNCTION_SPILLS_REMARK: &str = "SpillReloadCopies";
+/// Remark that describes the spills of a single loop (including its nested loops).
+pub const LOOP_SPILLS_REMARK: &str = "LoopSpillReloadCopies";
+
+#[derive(Debug)]
+pub struct LoopSpills<'a> {
+    pub remark: &'a Remark,
+    pub counts: SpillCounts,
+}
+
+#[derive(Debug)]
+pub struct FunctionSpills<'a> {
+    pub function: &'a Function,
+    /// Counts of the whole function. If the function-level remark was not loaded, the counts of
+    /// its loops are summed instead (see [`FunctionSpills::exact`]).
+    pub counts: SpillCounts,
+    /// Were the counts taken from a function-level remark? Summed loop counts ignore spills
+    /// outside of loops and count spills of nested loops multiple times.
+    pub exact: bool,
+    /// Loops sorted by hotness and then by their spill and reload count, in descending order.
+    pub loops: Vec<LoopSpills<'a>>,
+    /// Maximum hotness of the remarks.
+    pub hotness: Option<i32>,
+}
+
+/// Finds functions with spills, sorted by hotness and then by their spill and reload count, in
+/// descending order, so that register pressure hotspots are first.
+pub fn aggregate_spills<'a, I: IntoIterator<Item = &'a Remark>>(
+    remarks: I,
+) -> Vec<FunctionSpills<'a>> {
+    let mut functions: Map<&str, FunctionSpills> = Map::default();
+    for remark in remarks {
+        let Some(counts) = remark.spills else {
+            continue;
+        };
+        let key = remark
+            .function
+            .mangled_name
+            .as_deref()
+            .unwrap_or(&remark.function.name);
+        let function = functions.entry(key).or_insert_with(|| FunctionSpills {
+            function: &remark.function,
+            counts: SpillCounts::default(),
+            exact: false,
+            loops: vec![],
+            hotness: None,
+        });
+        function.hotness = function.hotness.max(remark.hotness);
+        if remark.name == LOOP_SPILLS_REMARK {
+            function.loops.push(LoopSpills { remark, counts });
+        } else if remark.name == FUNCTION_SPILLS_REMARK {
+            // The function may have been generated in multiple codegen units
+            if !function.exact || spill_count(&counts) > spill_count(&function.counts) {
+                function.counts = counts;
+                function.exact = true;
+            }
+        }
+    }
+
+    let mut functions: Vec<FunctionSpills> = functions
+        .into_values()
+        .map(|mut function| {
+            if !function.exact {
+                for spills in &function.loops {
+                    function.counts += spills.counts;
+                }
+            }
+            function.loops.sort_by(|a, b| {
+                b.remark
+                    .hotness
+                    .cmp(&a.remark.hotness)
+                    .then_with(|| spill_count(&b.counts).cmp(&spill_count(&a.counts)))
+            });
+            function
+        })
+        .filter(|function| function.counts != SpillCounts::default())
+        .collect();
+    functions.sort_by(|a, b| {
+        b.hotness
+            .cmp(&a.hotness)
+            .then_with(|| spill_count(&b.counts).cmp(&spill_count(&a.counts)))
+            .then_with(|| a.function.name.cmp(&b.function.name))
+    });
+    functions
+}
+
+/// Number of memory operations caused by register pressure.
+fn spill_count(counts: &SpillCounts) -> u64 {
+    counts.spills + counts.reloads
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remark::fixtures::remark;
+    use crate::remark::RemarkStage;
+    use crate::spills::aggregate_spills;
+
+    #[test]
+    fn spills_per_function() {
+        let regalloc = |name: &str, function: &str| {
+            remark("regalloc", name)
+                .in_function(function)
+                .unlocated()
+                .with_stage(RemarkStage::Codegen)
+        };
+        let remarks = vec![
+            regalloc("LoopSpillReloadCopies", "foo::small").with_spills(1, 1, 0),
+            regalloc("LoopSpillReloadCopies", "foo::main")
+                .with_spills(2, 3, 1)
+                .with_hotness(10),
+            regalloc("LoopSpillReloadCopies", "foo::main")
+                .with_spills(0, 4, 2)
+                .with_hotness(50),
+            regalloc("SpillReloadCopies", "foo::exact").with_spills(5, 8, 0),
+            regalloc("LoopSpillReloadCopies", "foo::exact").with_spills(1, 2, 0),
+            regalloc("SpillReloadCopies", "foo::copies").with_spills(0, 0, 3),
+        ];
+        let functions = aggregate_spills(&remarks);
+        insta::assert_snapshot!(functions.iter().map(|function| format!("{}: {} spills, {} reloads, {} copies, exact: {}, loops: {:?}", function.function.name, function.counts.spills, function.counts.reloads, function.counts.copies, function.exact, function.loops.iter().map(|spills| spills.remark.hotness).collect::<Vec<_>>())).collect::<Vec<_>>().join("\n"), @r###"
+        foo::main: 2 spills, 7 reloads, 3 copies, exact: false, loops: [Some(50), Some(10)]
+        foo::exact: 5 spills, 8 reloads, 0 copies, exact: true, loops: [None]
+        foo::small: 1 spills, 1 reloads, 0 copies, exact: false, loops: [None]
+        foo::copies: 0 spills, 0 reloads, 3 copies, exact: true, loops: []
+        "###);
+    }
+}
@@ -0,0 +1,292 @@
+//! Aggregated statistics of remarks.
+use crate::remark::Remark;
+use crate::utils::data_structures::Map;
+
+/// Number of remarks that share some property (e.g. a pass or a file).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct RemarkCount {
+    pub key: String,
+    pub count: usize,
+}
+
+/// Number of remarks with hotness in the range `[min, max]`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct HotnessBucket {
+    pub min: i32,
+    pub max: i32,
+    pub count: usize,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct RemarkStats {
+    pub total: usize,
+    /// Counts are sorted in descending order, ties are sorted by key.
+    pub by_pass: Vec<RemarkCount>,
+    pub by_name: Vec<RemarkCount>,
+    pub by_file: Vec<RemarkCount>,
+    pub by_function: Vec<RemarkCount>,
+    pub by_crate: Vec<RemarkCount>,
+    /// Functions sorted by the sum of hotness of their remarks.
+    pub by_function_hotness: Vec<RemarkCount>,
+    /// Histogram of hotness, with buckets of exponentially increasing size.
+    pub hotness_histogram: Vec<HotnessBucket>,
+    /// Number of remarks without hotness.
+    pub without_hotness: usize,
+}
+
+impl RemarkStats {
+    pub fn compute(remarks: &[Remark]) -> Self {
+        let mut by_pass: Map<&str, usize> = Map::default();
+        let mut by_name: Map<&str, usize> = Map::default();
+        let mut by_file: Map<&str, usize> = Map::default();
+        let mut by_function: Map<&str, usize> = Map::default();
+        let mut by_crate: Map<&str, usize> = Map::default();
+        let mut by_function_hotness: Map<&str, usize> = Map::default();
+        let mut buckets: Map<u32, usize> = Map::default();
+        let mut without_hotness = 0;
+
+        for remark in remarks {
+            *by_pass.entry(&remark.pass).or_default() += 1;
+            *by_name.entry(&remark.name).or_default() += 1;
+            if let Some(location) = &remark.function.location {
+                *by_file.entry(&location.file).or_default() += 1;
+            }
+            *by_function.entry(&remark.function.name).or_default() += 1;
+            *by_crate
+                .entry(crate_name(&remark.function.name))
+                .or_default() += 1;
+            match remark.hotness {
+                Some(hotness) => {
+                    let hotness = hotness.max(0) as u32;
+                    *by_function_hotness
+                        .entry(&remark.function.name)
+                        .or_default() += hotness as usize;
+                    *buckets.entry(bucket_index(hotness)).or_default() += 1;
+                }
+                None => without_hotness += 1,
+            }
+        }
+
+        let max_bucket = buckets.keys().copied().max();
+        let hotness_histogram = match max_bucket {
+            Some(max_bucket) => (0..=max_bucket)
+                .map(|index| {
+                    let (min, max) = bucket_range(index);
+                    HotnessBucket {
+                        min,
+                        max,
+                        count: buckets.get(&index).copied().unwrap_or(0),
+                    }
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        Self {
+            total: remarks.len(),
+            by_pass: sort_counts(by_pass),
+            by_name: sort_counts(by_name),
+            by_file: sort_counts(by_file),
+            by_function: sort_counts(by_function),
+            by_crate: sort_counts(by_crate),
+            by_function_hotness: sort_counts(by_function_hotness),
+            hotness_histogram,
+            without_hotness,
+        }
+    }
+}
+
+/// Returns the name of the crate that contains the function, based on its demangled path.
+/// For trait implementations (`<Type as Trait>::method`), the crate of the type is used.
+pub fn crate_name(function: &str) -> &str {
+    let path = function.trim_start_matches(['<', '&', '*']);
+    let path = path
+        .strip_prefix("mut ")
+        .or_else(|| path.strip_prefix("dyn "))
+        .unwrap_or(path);
+    match path.split_once("::") {
+        Some((name, _)) if !name.is_empty() && name.chars().all(is_identifier_char) => name,
+        _ => "(unknown)",
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Bucket 0 contains hotness 0, bucket `n` contains hotness in `[2^(n-1), 2^n - 1]`.
+fn bucket_index(hotness: u32) -> u32 {
+    u32::BITS - hotness.leading_zeros()
+}
+
+fn bucket_range(index: u32) -> (i32, i32) {
+    if index == 0 {
+        (0, 0)
+    } else {
+        let min = 1u64 << (index - 1);
+        let max = (1u64 << index) - 1;
+        (min as i32, max.min(i32::MAX as u64) as i32)
+    }
+}
+
+fn sort_counts(counts: Map<&str, usize>) -> Vec<RemarkCount> {
+    let mut counts: Vec<RemarkCount> = counts
+        .into_iter()
+        .map(|(key, count)| RemarkCount {
+            key: key.to_string(),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remark::fixtures::remark;
+    use crate::stats::{crate_name, RemarkStats};
+
+    #[test]
+    fn compute_stats() {
+        let remarks = vec![
+            remark("inline", "NoDefinition")
+                .in_function("foo::main")
+                .with_hotness(5),
+            remark("inline", "TooCostly")
+                .in_function("foo::main")
+                .with_hotness(0),
+            remark("inline", "NoDefinition").in_function("foo::bar"),
+            remark("gvn", "LoadClobbered")
+                .in_function("core::ptr::read")
+                .with_hotness(100),
+        ];
+        let stats = RemarkStats::compute(&remarks);
+        insta::assert_snapshot!(serde_json::to_string_pretty(&stats).unwrap(), @r###"
+        {
+          "total": 4,
+          "by_pass": [
+            {
+              "key": "inline",
+              "count": 3
+            },
+            {
+              "key": "gvn",
+              "count": 1
+            }
+          ],
+          "by_name": [
+            {
+              "key": "NoDefinition",
+              "count": 2
+            },
+            {
+              "key": "LoadClobbered",
+              "count": 1
+            },
+            {
+              "key": "TooCostly",
+              "count": 1
+            }
+          ],
+          "by_file": [
+            {
+              "key": "src/main.rs",
+              "count": 4
+            }
+          ],
+          "by_function": [
+            {
+              "key": "foo::main",
+              "count": 2
+            },
+            {
+              "key": "core::ptr::read",
+              "count": 1
+            },
+            {
+              "key": "foo::bar",
+              "count": 1
+            }
+          ],
+          "by_crate": [
+            {
+              "key": "foo",
+              "count": 3
+            },
+            {
+              "key": "core",
+              "count": 1
+            }
+          ],
+          "by_function_hotness": [
+            {
+              "key": "core::ptr::read",
+              "count": 100
+            },
+            {
+              "key": "foo::main",
+              "count": 5
+            }
+          ],
+          "hotness_histogram": [
+            {
+              "min": 0,
+              "max": 0,
+              "count": 1
+            },
+            {
+              "min": 1,
+              "max": 1,
+              "count": 0
+            },
+            {
+              "min": 2,
+              "max": 3,
+              "count": 0
+            },
+            {
+              "min": 4,
+              "max": 7,
+              "count": 1
+            },
+            {
+              "min": 8,
+              "max": 15,
+              "count": 0
+            },
+            {
+              "min": 16,
+              "max": 31,
+              "count": 0
+            },
+            {
+              "min": 32,
+              "max": 63,
+              "count": 0
+            },
+            {
+              "min": 64,
+              "max": 127,
+              "count": 1
+            }
+          ],
+          "without_hotness": 1
+        }
+        "###);
+    }
+
+    #[test]
+    fn crate_names() {
+        assert_eq!(crate_name("foo::bar::baz"), "foo");
+        assert_eq!(
+            crate_name("<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop"),
+            "alloc"
+        );
+        assert_eq!(
+            crate_name("<&mut foo::Bar as core::fmt::Debug>::fmt"),
+            "foo"
+        );
+        assert_eq!(crate_name("<[T] as core::fmt::Debug>::fmt"), "(unknown)");
+        assert_eq!(crate_name("main"), "(unknown)");
+    }
+}
@@ -1,7 +1,16 @@
 use colored::{ColoredString, Colorize};
+use std::io::IsTerminal;
 use std::path::Path;
 
 /// Formats a path in a unified format to be printed in CLI.
 pub fn cli_format_path<P: AsRef<Path>>(path: P) -> ColoredString {
     path.as_ref().display().to_string().yellow()
 }
+
+/// Disables terminal colors if exported output is written into a file (`output`), or if stdout is
+/// not a terminal (e.g. when it is redirected or piped).
+pub fn configure_output_colors(output: Option<&Path>) {
+    if output.is_some() || !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+}
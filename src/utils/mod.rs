@@ -9,18 +9,22 @@ pub mod io;
 pub mod timing;
 
 pub fn open_result(dir: &Path, open: bool) -> anyhow::Result<()> {
-    let index_path = dir.join(INDEX_FILE_PATH);
+    open_page(&dir.join(INDEX_FILE_PATH), open)
+}
+
+/// Opens a rendered page in a browser, or tells the user where to find it.
+pub fn open_page(path: &Path, open: bool) -> anyhow::Result<()> {
     if open {
-        opener::open_browser(&index_path).map_err(|error| {
+        opener::open_browser(path).map_err(|error| {
             anyhow::anyhow!(
                 "Could not open {} in browser: {error:?}",
-                cli_format_path(index_path)
+                cli_format_path(path)
             )
         })?;
     } else {
         log::info!(
             "Open {} in a browser to see the results.",
-            cli_format_path(index_path)
+            cli_format_path(path)
         );
     }
     Ok(())
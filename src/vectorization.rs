@@ -0,0 +1,292 @@
+//! Report of loop and SLP vectorization, with remarks grouped by their location.
+//!
+//! The vectorizers only emit a generic missed remark (e.g. `loop not vectorized`), the actual
+//! reasons are described by analysis remarks. Successfully vectorized loops are described by
+//! passed remarks. Both of these remark types have to be loaded explicitly.
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::remark::{Location, Remark, RemarkType};
+use crate::utils::data_structures::Map;
+
+/// Passes whose remarks are included in the report.
+pub const VECTORIZATION_PASSES: &[&str] = &["loop-vectorize", "slp-vectorizer"];
+
+static WIDTH_REGEX: OnceLock<Regex> = OnceLock::new();
+static INTERLEAVE_REGEX: OnceLock<Regex> = OnceLock::new();
+
+/// Category of a reason why code was not vectorized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FailureCategory {
+    UnknownTripCount,
+    UnsafeDependence,
+    Call,
+    FpReassociation,
+    ControlFlow,
+    NotBeneficial,
+    Other,
+}
+
+impl FailureCategory {
+    /// Returns the category of a missed or analysis vectorization remark, or `None` if the remark
+    /// does not describe a reason (e.g. the generic `loop not vectorized` remark).
+    pub fn from_remark(remark: &Remark) -> Option<Self> {
+        if remark.remark_type == RemarkType::Passed || remark.name == "MissedDetails" {
+            return None;
+        }
+        let category = match remark.name.as_str() {
+            "CantComputeNumberOfIterations" | "UnsupportedUncountableLoop" => {
+                Self::UnknownTripCount
+            }
+            "UnsafeDep" | "UnsafeMemDep" | "CantReorderMemOps" | "CantIdentifyArrayBounds" => {
+                Self::UnsafeDependence
+            }
+            "CantVectorizeCall" | "CantVectorizeLibcall" | "CantVectorizeIntrinsic" => Self::Call,
+            "CantReorderFPOps" => Self::FpReassociation,
+            "CFGNotUnderstood"
+            | "NoCFGForSelect"
+            | "LoopContainsSwitch"
+            | "NotInnermostLoop"
+            | "MultipleExitingBlocks"
+            | "EarlyExitNotLatchPredecessor" => Self::ControlFlow,
+            "NotBeneficial" | "VectorizationNotBeneficial" | "InterleavingNotBeneficial" => {
+                Self::NotBeneficial
+            }
+            // Remarks parsed from text diagnostics do not have a name, so the message is used
+            _ => Self::from_message(&remark.message_text()),
+        };
+        Some(category)
+    }
+
+    fn from_message(message: &str) -> Self {
+        let message = message.to_lowercase();
+        if message.contains("number of loop iterations") {
+            Self::UnknownTripCount
+        } else if message.contains("unsafe dependent memory")
+            || message.contains("array bounds")
+            || message.contains("memory operations")
+        {
+            Self::UnsafeDependence
+        } else if message.contains("call instruction") {
+            Self::Call
+        } else if message.contains("floating-point") {
+            Self::FpReassociation
+        } else if message.contains("control flow") || message.contains("switch statement") {
+            Self::ControlFlow
+        } else if message.contains("not beneficial") {
+            Self::NotBeneficial
+        } else {
+            Self::Other
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::UnknownTripCount => "Unknown trip count",
+            Self::UnsafeDependence => "Unsafe memory dependencies",
+            Self::Call => "Calls",
+            Self::FpReassociation => "Floating-point reassociation",
+            Self::ControlFlow => "Control flow",
+            Self::NotBeneficial => "Not beneficial",
+            Self::Other => "Other",
+        }
+    }
+}
+
+/// Vectorization described by a passed remark.
+#[derive(Debug)]
+pub struct Vectorization<'a> {
+    pub remark: &'a Remark,
+    /// Vectorization factor (width), e.g. `4`.
+    pub factor: Option<String>,
+    pub interleave_count: Option<String>,
+}
+
+impl<'a> Vectorization<'a> {
+    fn new(remark: &'a Remark) -> Self {
+        let message = remark.message_text();
+        let find = |regex: &OnceLock<Regex>, pattern: &str| {
+            regex
+                .get_or_init(|| Regex::new(pattern).expect("Could not create regular expression"))
+                .captures(&message)
+                .map(|captures| captures[1].trim().to_string())
+        };
+        Self {
+            remark,
+            factor: find(&WIDTH_REGEX, r"vectorization width: ([^,)]+)"),
+            interleave_count: find(&INTERLEAVE_REGEX, r"interleaved count: (\d+)"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorizationFailure<'a> {
+    pub remark: &'a Remark,
+    pub category: FailureCategory,
+}
+
+/// Vectorization remarks of a single location (usually a loop).
+#[derive(Debug)]
+pub struct VectorizationEntry<'a> {
+    pub function: &'a str,
+    pub location: Option<&'a Location>,
+    pub vectorizations: Vec<Vectorization<'a>>,
+    pub failures: Vec<VectorizationFailure<'a>>,
+    /// Remarks without a reason (e.g. `loop not vectorized`).
+    pub missed: Vec<&'a Remark>,
+    /// Maximum hotness of the remarks.
+    pub hotness: Option<i32>,
+}
+
+impl<'a> VectorizationEntry<'a> {
+    pub fn is_vectorized(&self) -> bool {
+        !self.vectorizations.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct VectorizationReport<'a> {
+    /// Entries sorted by hotness in descending order and then by location.
+    pub entries: Vec<VectorizationEntry<'a>>,
+    /// Number of failures in each category, sorted in descending order.
+    pub categories: Vec<(FailureCategory, usize)>,
+}
+
+impl<'a> VectorizationReport<'a> {
+    /// Creates a report from the remarks of vectorization passes, other remarks are ignored.
+    pub fn from_remarks<I: IntoIterator<Item = &'a Remark>>(remarks: I) -> Self {
+        type Key<'a> = (&'a str, Option<(&'a str, u32, u32)>);
+        let mut entries: Map<Key, VectorizationEntry> = Map::default();
+        for remark in remarks {
+            if !VECTORIZATION_PASSES.contains(&remark.pass.as_str()) {
+                continue;
+            }
+            let location = remark.function.location.as_ref();
+            let key = (
+                remark.function.name.as_str(),
+                location.map(|location| (location.file.as_str(), location.line, location.column)),
+            );
+            let entry = entries.entry(key).or_insert_with(|| VectorizationEntry {
+                function: &remark.function.name,
+                location,
+                vectorizations: vec![],
+                failures: vec![],
+                missed: vec![],
+                hotness: None,
+            });
+            entry.hotness = entry.hotness.max(remark.hotness);
+            if remark.remark_type == RemarkType::Passed {
+                entry.vectorizations.push(Vectorization::new(remark));
+            } else if let Some(category) = FailureCategory::from_remark(remark) {
+                entry
+                    .failures
+                    .push(VectorizationFailure { remark, category });
+            } else {
+                entry.missed.push(remark);
+            }
+        }
+
+        let mut categories: Map<FailureCategory, usize> = Map::default();
+        for failure in entries.values().flat_map(|entry| &entry.failures) {
+            *categories.entry(failure.category).or_default() += 1;
+        }
+        let mut categories: Vec<(FailureCategory, usize)> = categories.into_iter().collect();
+        categories.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut entries: Vec<VectorizationEntry> = entries.into_values().collect();
+        entries.sort_by(|a, b| {
+            let location = |entry: &VectorizationEntry<'a>| {
+                entry
+                    .location
+                    .map(|location| (&location.file, location.line, location.column))
+            };
+            b.hotness
+                .cmp(&a.hotness)
+                .then_with(|| location(a).cmp(&location(b)))
+                .then_with(|| a.function.cmp(b.function))
+        });
+        Self {
+            entries,
+            categories,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remark::fixtures::remark;
+    use crate::remark::RemarkType;
+    use crate::vectorization::{FailureCategory, VectorizationReport};
+
+    #[test]
+    fn group_by_location() {
+        let remarks = vec![
+            remark("loop-vectorize", "Vectorized")
+                .at("src/main.rs", 1)
+                .with_text("vectorized loop (vectorization width: 4, interleaved count: 2)")
+                .with_type(RemarkType::Passed),
+            remark("loop-vectorize", "MissedDetails")
+                .at("src/main.rs", 5)
+                .with_text("loop not vectorized"),
+            remark("loop-vectorize", "CantComputeNumberOfIterations")
+                .at("src/main.rs", 5)
+                .with_text("loop not vectorized: could not determine number of loop iterations")
+                .with_type(RemarkType::Analysis),
+            remark("loop-vectorize", "CantReorderFPOps")
+                .at("src/main.rs", 5)
+                .with_text("loop not vectorized: cannot prove it is safe to reorder floating-point operations")
+                .with_type(RemarkType::Analysis),
+            remark("slp-vectorizer", "NotBeneficial")
+                .at("src/main.rs", 8)
+                .with_text("List vectorization was possible but not beneficial with cost 0 >= 0"),
+            remark("inline", "NoDefinition")
+                .at("src/main.rs", 8)
+                .with_text("foo will not be inlined"),
+        ];
+        let report = VectorizationReport::from_remarks(&remarks);
+        let output = report
+            .entries
+            .iter()
+            .map(|entry| {
+                let status = match entry.vectorizations.first() {
+                    Some(vectorization) => format!(
+                        "vectorized (factor {:?}, interleave {:?})",
+                        vectorization.factor, vectorization.interleave_count
+                    ),
+                    None => "not vectorized".to_string(),
+                };
+                let reasons = entry
+                    .failures
+                    .iter()
+                    .map(|failure| failure.category.description())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "{}: {status} [{reasons}], {} missed",
+                    entry.location.unwrap().line,
+                    entry.missed.len()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        insta::assert_snapshot!(output, @r###"
+        1: vectorized (factor Some("4"), interleave Some("2")) [], 0 missed
+        5: not vectorized [Unknown trip count, Floating-point reassociation], 1 missed
+        8: not vectorized [Not beneficial], 0 missed
+        "###);
+        assert_eq!(report.categories.len(), 3);
+    }
+
+    #[test]
+    fn category_from_message() {
+        let remark = remark("loop-vectorize", "loop-vectorize")
+            .at("src/main.rs", 1)
+            .with_text("loop not vectorized: call instruction cannot be vectorized")
+            .with_type(RemarkType::Analysis);
+        assert_eq!(
+            FailureCategory::from_remark(&remark),
+            Some(FailureCategory::Call)
+        );
+    }
+}
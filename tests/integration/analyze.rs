@@ -1,4 +1,6 @@
-use crate::utils::{analyze_remarks, get_test_data_path, HTMLDir, OutputExt};
+use std::path::{Path, PathBuf};
+
+use crate::utils::{analyze_remarks, export_remarks_jsonl, get_test_data_path, HTMLDir, OutputExt};
 
 #[test]
 fn analyze_directory() -> anyhow::Result<()> {
@@ -22,24 +24,668 @@ fn analyze_directory() -> anyhow::Result<()> {
 
 #[test]
 fn create_source_file() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let output_dir = render_similarity_join(dir.path())?;
+    let dir = HTMLDir::new(&output_dir);
+    dir.check_source("src_main.rs.html");
+    dir.check_source("src_record.rs.html");
+
+    Ok(())
+}
+
+#[test]
+fn statistics_page() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let output_dir = render_similarity_join(dir.path())?;
+
+    let stats = std::fs::read_to_string(output_dir.join("stats.html"))?;
+    assert!(stats.contains("Remarks by kind"));
+    assert!(stats.contains("<a href='src/src_main.rs.html'>"));
+
+    Ok(())
+}
+
+#[test]
+fn function_pages() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let output_dir = render_similarity_join(dir.path())?;
+
+    let remark_list = std::fs::read_to_string(output_dir.join("remarks.html"))?;
+    assert!(remark_list.contains("<a href='fn/0.html'>"));
+    let function_page = std::fs::read_to_string(output_dir.join("fn/0.html"))?;
+    assert!(function_page.contains("<h4>Remarks</h4>"));
+
+    Ok(())
+}
+
+#[test]
+fn inlining_graph() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let output_dir = render_similarity_join(dir.path())?;
+
+    let inlining = std::fs::read_to_string(output_dir.join("inlining.html"))?;
+    assert!(inlining.contains("const graph = {"));
+    let dot = std::fs::read_to_string(output_dir.join("inlining.dot"))?;
+    assert!(dot.starts_with("digraph inlining {"));
+
+    Ok(())
+}
+
+#[test]
+fn near_miss_inlining() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let output_dir = render_similarity_join(dir.path())?;
+
+    let near_misses = std::fs::read_to_string(output_dir.join("near-misses.html"))?;
+    assert!(near_misses.contains("byteorder::io::ReadBytesExt::read_u32"));
+    assert!(near_misses.contains("155 (29.5%)"));
+
+    Ok(())
+}
+
+#[test]
+fn export_sarif() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let output = analyze_remarks(
+        dir.path(),
+        &[
+            "--source-dir",
+            dir.path().to_str().unwrap(),
+            "--external",
+            "--format",
+            "sarif",
+            get_test_data_path("remarks-1").to_str().unwrap(),
+        ],
+    )?
+    .assert_ok();
+
+    let log: serde_json::Value = serde_json::from_str(&output.stdout())?;
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert!(!run["tool"]["driver"]["rules"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+    assert!(!run["results"].as_array().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn export_dot() -> anyhow::Result<()> {
     let data_dir = get_test_data_path("remarks-similarity-join");
     let dir = tempfile::TempDir::new()?;
-    let output_dir = "output";
+    let output = analyze_remarks(
+        dir.path(),
+        &[
+            "--source-dir",
+            data_dir.to_str().unwrap(),
+            "--external",
+            "--format",
+            "dot",
+            data_dir.join("yaml").to_str().unwrap(),
+        ],
+    )?
+    .assert_ok();
+
+    let stdout = output.stdout();
+    assert!(stdout.starts_with("digraph inlining {"));
+    assert!(stdout.contains(" -> "));
+    assert!(stdout.contains("[label=\"NoDefinition"));
+
+    Ok(())
+}
 
+#[test]
+fn vectorization_report() -> anyhow::Result<()> {
+    let data_dir = get_test_data_path("remarks-similarity-join");
+    let dir = tempfile::TempDir::new()?;
     analyze_remarks(
         dir.path(),
         &[
             "--source-dir",
             data_dir.to_str().unwrap(),
+            "--external",
+            "--passed",
+            "--analysis",
             "--output-dir",
-            output_dir,
+            "output",
             data_dir.join("yaml").to_str().unwrap(),
         ],
     )?
     .assert_ok();
-    let dir = HTMLDir::new(&dir.path().join(output_dir));
-    dir.check_source("src_main.rs.html");
-    dir.check_source("src_record.rs.html");
+
+    let report = std::fs::read_to_string(dir.path().join("output").join("vectorization.html"))?;
+    assert!(report.contains("Vectorized (factor 2, interleave count 2)"));
+    assert!(report.contains("<b>Unknown trip count</b>: loop not vectorized"));
+    assert!(!report.contains("were not loaded"));
+
+    Ok(())
+}
+
+#[test]
+fn export_metrics_json() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let output = analyze_remarks(
+        dir.path(),
+        &[
+            "--source-dir",
+            dir.path().to_str().unwrap(),
+            "--external",
+            "--format",
+            "metrics-json",
+            get_test_data_path("remarks-1").to_str().unwrap(),
+        ],
+    )?
+    .assert_ok();
+
+    let document: serde_json::Value = serde_json::from_str(&output.stdout())?;
+    assert_eq!(document["schema_version"], 1);
+    let functions = document["functions"].as_array().unwrap();
+    assert!(!functions.is_empty());
+    assert!(functions
+        .iter()
+        .all(|function| function["function"]["name"].is_string()));
+    assert!(functions
+        .iter()
+        .any(|function| function["stack_size"].is_u64() && function["instruction_count"].is_u64()));
+
+    Ok(())
+}
+
+#[test]
+fn function_metrics_page() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let remark_dir = get_test_data_path("remarks-1");
+    let render = |analysis: bool| -> anyhow::Result<()> {
+        let mut args = vec![
+            "--source-dir",
+            dir.path().to_str().unwrap(),
+            "--external",
+            "--output-dir",
+            "output",
+            remark_dir.to_str().unwrap(),
+        ];
+        if analysis {
+            args.push("--analysis");
+        }
+        analyze_remarks(dir.path(), &args)?.assert_ok();
+        Ok(())
+    };
+    let read_page = |name: &str| std::fs::read_to_string(dir.path().join("output").join(name));
+
+    render(false)?;
+    assert!(read_page("metrics.html")?.contains("use the <code>--analysis</code> flag"));
+    assert!(read_page("generic-bloat.html")?.contains("No instruction counts were found"));
+
+    render(true)?;
+    let page = read_page("metrics.html")?;
+    assert!(page.contains("const metrics = ["));
+    assert!(page.contains("__rust_alloc"));
+    assert!(read_page("generic-bloat.html")?
+        .contains("No generic functions with multiple instantiations were found"));
 
     Ok(())
 }
+
+#[test]
+fn function_metrics_from_codegen_file() -> anyhow::Result<()> {
+    // The function is only located by a remark in the optimization remark file, while its
+    // metrics are in the codegen remark file of the same codegen unit
+    let dir = tempfile::TempDir::new()?;
+    std::fs::create_dir(dir.path().join("src"))?;
+    std::fs::write(
+        dir.path().join("src/main.rs"),
+        "fn main() {\n    foo();\n}\n",
+    )?;
+    let remark_dir = dir.path().join("remarks");
+    std::fs::create_dir(&remark_dir)?;
+    std::fs::write(
+        remark_dir.join("a.cgu.0.opt.opt.yaml"),
+        r#"--- !Missed
+Pass:            inline
+Name:            NoDefinition
+DebugLoc:        { File: src/main.rs, Line: 2, Column: 5 }
+Function:        _ZN3foo4main17h0123456789abcdefE
+Args:
+  - Callee:          _ZN3foo3foo17h0123456789abcdefE
+  - String:          ' will not be inlined into '
+  - Caller:          _ZN3foo4main17h0123456789abcdefE
+...
+"#,
+    )?;
+    std::fs::write(
+        remark_dir.join("a.cgu.0.codegen.opt.yaml"),
+        r#"--- !Analysis
+Pass:            prologepilog
+Name:            StackSize
+Function:        _ZN3foo4main17h0123456789abcdefE
+Args:
+  - NumStackBytes:   '123'
+  - String:          ' stack bytes in function'
+...
+"#,
+    )?;
+
+    analyze_remarks(
+        dir.path(),
+        &[
+            "--source-dir",
+            dir.path().to_str().unwrap(),
+            "--output-dir",
+            "output",
+            "--analysis",
+            remark_dir.to_str().unwrap(),
+        ],
+    )?
+    .assert_ok();
+    let page = std::fs::read_to_string(dir.path().join("output/metrics.html"))?;
+    assert!(!page.contains("No function metrics were found"));
+    assert!(page.contains("foo::main"));
+    assert!(page.contains("\"stack_size\":123"));
+
+    Ok(())
+}
+
+#[test]
+fn generic_bloat_page() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let remark_dir = dir.path().join("remarks");
+    std::fs::create_dir(&remark_dir)?;
+    let remark = |function: &str, instructions: u32| {
+        format!(
+            r#"--- !Analysis
+Pass:            asm-printer
+Name:            InstructionCount
+DebugLoc:        {{ File: 'src/main.rs', Line: 1, Column: 0 }}
+Function:        {function}
+Args:
+  - NumInstructions: '{instructions}'
+  - String:          ' instructions in function'
+...
+"#
+        )
+    };
+    std::fs::write(
+        remark_dir.join("foo.opt.yaml"),
+        [
+            remark("_ZN4core3ptr13drop_in_place17h1111111111111111E", 10),
+            remark("_ZN4core3ptr13drop_in_place17h2222222222222222E", 30),
+            remark(
+                "_ZN3foo3run28_$u7b$$u7b$closure$u7d$$u7d$17h3333333333333333E",
+                40,
+            ),
+            remark(
+                "_ZN3foo3run28_$u7b$$u7b$closure$u7d$$u7d$17h4444444444444444E",
+                50,
+            ),
+        ]
+        .concat(),
+    )?;
+
+    analyze_remarks(
+        dir.path(),
+        &[
+            "--source-dir",
+            dir.path().to_str().unwrap(),
+            "--external",
+            "--analysis",
+            "--output-dir",
+            "output",
+            remark_dir.to_str().unwrap(),
+        ],
+    )?
+    .assert_ok();
+
+    let page = std::fs::read_to_string(dir.path().join("output").join("generic-bloat.html"))?;
+    assert!(page.contains("<code>core::ptr::drop_in_place</code>"));
+    assert!(page.contains("_ZN4core3ptr13drop_in_place17h1111111111111111E"));
+    assert!(page.contains("_ZN4core3ptr13drop_in_place17h2222222222222222E"));
+    assert!(!page.contains("closure"));
+
+    Ok(())
+}
+
+#[test]
+fn spills_report() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let remark_dir = get_test_data_path("remarks-1");
+    let render = |filter: Option<&str>| -> anyhow::Result<(String, String)> {
+        let mut args = vec![
+            "--source-dir",
+            dir.path().to_str().unwrap(),
+            "--external",
+            "--output-dir",
+            "output",
+            remark_dir.to_str().unwrap(),
+        ];
+        if let Some(filter) = filter {
+            args.extend(["--filter", filter]);
+        }
+        analyze_remarks(dir.path(), &args)?.assert_ok();
+        let read_page = |name: &str| std::fs::read_to_string(dir.path().join("output").join(name));
+        Ok((read_page("spills.html")?, read_page("remarks.html")?))
+    };
+
+    // `SpillReloadCopies` remarks are filtered by default, but they are still used by the report
+    let (page, remark_list) = render(None)?;
+    assert!(!page.contains("No spills were found."));
+    assert!(page.contains("main"));
+    assert!(!page.contains("were summed from the loops"));
+    assert!(!remark_list.contains(r#""SpillReloadCopies""#));
+
+    let (page, remark_list) = render(Some("FastISelFailure"))?;
+    assert!(page.contains(">main</a>"));
+    assert!(!page.contains("were summed from the loops"));
+    assert!(remark_list.contains(r#""SpillReloadCopies""#));
+
+    Ok(())
+}
+
+#[test]
+fn explain_remarks() -> anyhow::Result<()> {
+    let data_dir = get_test_data_path("remarks-similarity-join");
+    let remark_dir = data_dir.join("yaml");
+    let dir = tempfile::TempDir::new()?;
+    std::fs::write(
+        dir.path().join("knowledge.toml"),
+        r#"
+[[entry]]
+pass = "inline"
+name = "TooCostly"
+explanation = "Custom explanation"
+suggestions = ["Custom suggestion"]
+"#,
+    )?;
+    let run = |args: &[&str]| -> anyhow::Result<String> {
+        let mut arguments = vec![
+            "--source-dir",
+            data_dir.to_str().unwrap(),
+            "--external",
+            remark_dir.to_str().unwrap(),
+        ];
+        arguments.extend(args);
+        Ok(analyze_remarks(dir.path(), &arguments)?
+            .assert_ok()
+            .stdout())
+    };
+
+    let output = run(&["--format", "text"])?;
+    assert!(output.contains("= note: The function could not be inlined"));
+    assert!(output.contains("= help: Enable link-time optimization"));
+
+    let output = run(&["--format", "text", "--knowledge-file", "knowledge.toml"])?;
+    assert!(output.contains("= note: Custom explanation"));
+    assert!(output.contains("= help: Custom suggestion"));
+    assert!(output.contains("= note: The function could not be inlined"));
+
+    run(&["--output-dir", "output"])?;
+    let remark_list = std::fs::read_to_string(dir.path().join("output").join("remarks.html"))?;
+    assert!(remark_list.contains("const explanations = [\"<div>"));
+    assert!(remark_list.contains("\"explanation\":0"));
+
+    Ok(())
+}
+
+#[test]
+fn categorize_panic_paths() -> anyhow::Result<()> {
+    let data_dir = get_test_data_path("remarks-similarity-join");
+    let remark_dir = data_dir.join("yaml");
+    let dir = tempfile::TempDir::new()?;
+    let output = analyze_remarks(
+        dir.path(),
+        &[
+            "--source-dir",
+            data_dir.to_str().unwrap(),
+            "--external",
+            "--format",
+            "jsonl",
+            remark_dir.to_str().unwrap(),
+        ],
+    )?
+    .assert_ok()
+    .stdout();
+
+    let remarks = output
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()?;
+    let bounds_check = remarks
+        .iter()
+        .find(|remark| remark["category"] == "bounds-check")
+        .expect("No bounds check remark found");
+    assert_eq!(bounds_check["pass"], "inline");
+    assert!(remarks.iter().any(|remark| remark["category"].is_null()));
+
+    analyze_remarks(
+        dir.path(),
+        &[
+            "--source-dir",
+            data_dir.to_str().unwrap(),
+            "--external",
+            "--output-dir",
+            "output",
+            remark_dir.to_str().unwrap(),
+        ],
+    )?
+    .assert_ok();
+    let remark_list = std::fs::read_to_string(dir.path().join("output").join("remarks.html"))?;
+    assert!(remark_list.contains("id=\"category-filter\""));
+    assert!(remark_list.contains("\"category\":\"bounds-check\""));
+
+    Ok(())
+}
+
+#[test]
+fn export_json_lines() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    analyze_remarks(
+        dir.path(),
+        &[
+            "--source-dir",
+            dir.path().to_str().unwrap(),
+            "--external",
+            "--format",
+            "jsonl",
+            "--output",
+            "remarks.jsonl",
+            get_test_data_path("remarks-1").to_str().unwrap(),
+        ],
+    )?
+    .assert_ok();
+
+    let output = std::fs::read_to_string(dir.path().join("remarks.jsonl"))?;
+    assert!(output.lines().count() > 0);
+    for line in output.lines() {
+        let remark: serde_json::Value = serde_json::from_str(line)?;
+        assert_eq!(remark["schema_version"], 1);
+        assert!(remark["function"]["mangled_name"].is_string());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn export_text_sort_hotness() -> anyhow::Result<()> {
+    let data_dir = get_test_data_path("remarks-similarity-join");
+    let dir = tempfile::TempDir::new()?;
+    let output = analyze_remarks(
+        dir.path(),
+        &[
+            "--source-dir",
+            data_dir.to_str().unwrap(),
+            "--format",
+            "text",
+            "--sort",
+            "hotness",
+            data_dir.join("yaml").to_str().unwrap(),
+        ],
+    )?
+    .assert_ok();
+
+    let stdout = output.stdout();
+    assert!(stdout.contains("remark["));
+    assert!(stdout.contains(" --> src/"));
+    // Colors are not used when stdout is not a terminal
+    assert!(!stdout.contains('\u{1b}'));
+
+    Ok(())
+}
+
+#[test]
+fn export_text_hotness_order() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let remark_dir = dir.path().join("remarks");
+    std::fs::create_dir(&remark_dir)?;
+    let remark = |function: &str, hotness: u32| {
+        format!(
+            r#"--- !Missed
+Pass:            inline
+Name:            NoDefinition
+DebugLoc:        {{ File: 'src/main.rs', Line: {hotness}, Column: 5 }}
+Function:        {function}
+Hotness:         {hotness}
+Args:
+  - String:          'call will not be inlined'
+...
+"#
+        )
+    };
+    std::fs::write(
+        remark_dir.join("foo.opt.opt.yaml"),
+        [remark("cold", 10), remark("hot", 300), remark("warm", 200)].concat(),
+    )?;
+
+    let output = analyze_remarks(
+        dir.path(),
+        &[
+            "--source-dir",
+            dir.path().to_str().unwrap(),
+            "--external",
+            "--format",
+            "text",
+            "--sort",
+            "hotness",
+            remark_dir.to_str().unwrap(),
+        ],
+    )?
+    .assert_ok();
+
+    let hotness: Vec<String> = output
+        .stdout()
+        .lines()
+        .filter_map(|line| line.split_once("= hotness: "))
+        .map(|(_, hotness)| hotness.to_string())
+        .collect();
+    assert_eq!(hotness, vec!["300", "200", "10"]);
+
+    Ok(())
+}
+
+#[test]
+fn suppress_acknowledged_remarks() -> anyhow::Result<()> {
+    let data_dir = get_test_data_path("remarks-similarity-join");
+    let dir = tempfile::TempDir::new()?;
+    let remark_dir = data_dir.join("yaml");
+    let export = |args: &[&str]| export_remarks_jsonl(dir.path(), &data_dir, &remark_dir, args);
+
+    let remarks = export(&[])?;
+    let function = remarks[0]["function"]["name"].as_str().unwrap();
+    let kind = remarks[0]["name"].as_str().unwrap();
+    let ack_file = dir.path().join("ack.toml");
+    std::fs::write(
+        &ack_file,
+        format!("[[remark]]\nfunction = {function:?}\nkind = {kind:?}\nreason = \"Known\"\n"),
+    )?;
+
+    let kept = export(&["--ack-file", ack_file.to_str().unwrap()])?;
+    assert!(kept.len() < remarks.len());
+    assert!(!kept
+        .iter()
+        .any(|remark| remark["function"]["name"] == function && remark["name"] == kind));
+
+    Ok(())
+}
+
+#[test]
+fn filter_changed_since() -> anyhow::Result<()> {
+    let data_dir = get_test_data_path("remarks-similarity-join");
+    let dir = tempfile::TempDir::new()?;
+    let source_dir = dir.path().join("source");
+    std::fs::create_dir_all(source_dir.join("src"))?;
+    for entry in std::fs::read_dir(data_dir.join("src"))? {
+        let entry = entry?;
+        std::fs::copy(entry.path(), source_dir.join("src").join(entry.file_name()))?;
+    }
+    let git = |args: &[&str]| -> anyhow::Result<()> {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(&source_dir)
+            .status()?;
+        assert!(status.success());
+        Ok(())
+    };
+    git(&["init", "--quiet"])?;
+    git(&["add", "."])?;
+    git(&["commit", "--quiet", "-m", "Initial commit"])?;
+
+    let remark_dir = data_dir.join("yaml");
+    let export = |args: &[&str]| export_remarks_jsonl(dir.path(), &source_dir, &remark_dir, args);
+
+    assert!(export(&["--changed-since", "HEAD"])?.is_empty());
+
+    // Change a line with a remark
+    let remarks = export(&[])?;
+    let location = &remarks[0]["function"]["location"];
+    let file = location["file"].as_str().unwrap();
+    let line = location["line"].as_u64().unwrap();
+    let path = source_dir.join(file);
+    let content = std::fs::read_to_string(&path)?
+        .lines()
+        .enumerate()
+        .map(|(index, content)| {
+            if index as u64 + 1 == line {
+                format!("{content} // changed\n")
+            } else {
+                format!("{content}\n")
+            }
+        })
+        .collect::<String>();
+    std::fs::write(&path, content)?;
+
+    let changed = export(&["--changed-since", "HEAD"])?;
+    assert!(!changed.is_empty());
+    let on_line =
+        |location: &serde_json::Value| location["file"] == file && location["line"] == line;
+    for remark in changed {
+        assert!(
+            on_line(&remark["function"]["location"])
+                || remark["message"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .any(|part| on_line(&part["location"]))
+        );
+    }
+
+    Ok(())
+}
+
+/// Renders the website of the `remarks-similarity-join` test data into `dir` and returns the
+/// output directory.
+fn render_similarity_join(dir: &Path) -> anyhow::Result<PathBuf> {
+    let data_dir = get_test_data_path("remarks-similarity-join");
+    analyze_remarks(
+        dir,
+        &[
+            "--source-dir",
+            data_dir.to_str().unwrap(),
+            "--output-dir",
+            "output",
+            data_dir.join("yaml").to_str().unwrap(),
+        ],
+    )?
+    .assert_ok();
+    Ok(dir.join("output"))
+}
@@ -1,4 +1,5 @@
-use crate::utils::{cargo_remark, init_cargo_project, OutputExt};
+use crate::utils::{cargo_remark, init_cargo_project, remark_dir_supported, OutputExt};
+use cargo_remark::build::{RemarkBuilder, RemarkMode};
 use cargo_remark::remark::{load_remarks_from_dir, Location, Remark, RemarkLoadOptions};
 use std::path::Path;
 
@@ -13,6 +14,9 @@ fn main() {
 
 #[test]
 fn test_build_filter() -> anyhow::Result<()> {
+    if !remark_dir_supported() {
+        return Ok(());
+    }
     let mut project = init_cargo_project()?;
     project.file("src/main.rs", INLINE_NEVER_SOURCE);
     cargo_remark(&project.dir, &["build", "--filter", "NeverInline"])?.assert_ok();
@@ -22,6 +26,9 @@ fn test_build_filter() -> anyhow::Result<()> {
 
 #[test]
 fn test_generate_remarks() -> anyhow::Result<()> {
+    if !remark_dir_supported() {
+        return Ok(());
+    }
     let mut project = init_cargo_project()?;
     project.file("src/main.rs", INLINE_NEVER_SOURCE);
     cargo_remark(&project.dir, &["build", "--filter", ""])?.assert_ok();
@@ -37,6 +44,7 @@ fn test_generate_remarks() -> anyhow::Result<()> {
             source_dir: project.dir.clone(),
             filter_kind: vec![],
             rustc_source_root: None,
+            ..Default::default()
         },
         None,
     )?;
@@ -64,6 +72,46 @@ fn test_generate_remarks() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_build_invalid_toolchain() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file("src/main.rs", INLINE_NEVER_SOURCE);
+    cargo_remark(
+        &project.dir,
+        &["build", "--toolchain", "cargo-remark-nonexistent-toolchain"],
+    )?
+    .assert_error();
+    Ok(())
+}
+
+#[test]
+fn test_build_api() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file("src/main.rs", INLINE_NEVER_SOURCE);
+
+    let output = RemarkBuilder::new()
+        .manifest_path(project.path("Cargo.toml"))
+        .passes(["inline"])
+        .mode(RemarkMode::Diagnostics)
+        .build()?;
+    assert!(output.yaml_dir().is_none());
+    assert_eq!(output.metadata.packages[0].name, "foo");
+
+    let remarks = output.load_remarks(
+        RemarkLoadOptions {
+            source_dir: output.source_dir.clone(),
+            ..Default::default()
+        },
+        None,
+    )?;
+    assert!(remarks.iter().all(|remark| remark.pass == "inline"));
+    assert!(remarks
+        .iter()
+        .any(|remark| remark.function.name == "foo::main"));
+
+    Ok(())
+}
+
 fn normalize_location(location: Option<&Location>) -> Option<Location> {
     location.map(|l| Location {
         file: l.file.replace('\\', "/"),
@@ -80,6 +128,7 @@ fn load_remarks(path: &Path, filter: Vec<String>) -> Vec<Remark> {
             source_dir: path.to_path_buf(),
             filter_kind: filter,
             rustc_source_root: None,
+            ..Default::default()
         },
         None,
     )
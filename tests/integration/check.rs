@@ -0,0 +1,145 @@
+use crate::utils::{cargo_remark, init_cargo_project, OutputExt};
+
+const INLINE_NEVER_SOURCE: &str = r#"
+#[inline(never)]
+fn foo() {}
+
+fn main() {
+    foo();
+}
+"#;
+
+const INLINE_NEVER_SOURCE_2: &str = r#"
+#[inline(never)]
+fn foo() {}
+
+#[inline(never)]
+fn bar() {}
+
+fn main() {
+    foo();
+    bar();
+}
+"#;
+
+#[test]
+fn check_without_baseline() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file("src/main.rs", INLINE_NEVER_SOURCE);
+    cargo_remark(&project.dir, &["check"])?.assert_error();
+    Ok(())
+}
+
+#[test]
+fn check_baseline() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file("src/main.rs", INLINE_NEVER_SOURCE);
+    cargo_remark(
+        &project.dir,
+        &["check", "--save-baseline", "baseline.json", "--filter", ""],
+    )?
+    .assert_ok();
+    assert!(project.path("baseline.json").is_file());
+
+    cargo_remark(
+        &project.dir,
+        &["check", "--baseline", "baseline.json", "--filter", ""],
+    )?
+    .assert_ok();
+
+    project.file("src/main.rs", INLINE_NEVER_SOURCE_2);
+    cargo_remark(
+        &project.dir,
+        &["check", "--baseline", "baseline.json", "--filter", ""],
+    )?
+    .assert_error();
+
+    // New remarks in functions that are not denied do not fail the check
+    cargo_remark(
+        &project.dir,
+        &[
+            "check",
+            "--baseline",
+            "baseline.json",
+            "--filter",
+            "",
+            "--deny-function",
+            "^nonexistent$",
+        ],
+    )?
+    .assert_ok();
+
+    Ok(())
+}
+
+#[test]
+fn check_message_format_json() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file("src/main.rs", INLINE_NEVER_SOURCE);
+    let output = cargo_remark(
+        &project.dir,
+        &[
+            "check",
+            "--message-format",
+            "json",
+            "--filter",
+            "",
+            "--external",
+        ],
+    )?
+    .assert_ok();
+
+    let stdout = output.stdout();
+    let messages: Vec<_> =
+        cargo_metadata::Message::parse_stream(stdout.as_bytes()).collect::<Result<_, _>>()?;
+    assert!(!messages.is_empty());
+    for message in messages {
+        let cargo_metadata::Message::CompilerMessage(message) = message else {
+            panic!("Unexpected message {message:?}");
+        };
+        assert_eq!(message.target.name, "foo");
+        let diagnostic = message.message;
+        assert_eq!(
+            diagnostic.level,
+            cargo_metadata::diagnostic::DiagnosticLevel::Note
+        );
+        assert_eq!(diagnostic.spans[0].file_name, "src/main.rs");
+        assert!(diagnostic.spans[0].line_start > 0);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn check_expectations() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file(
+        "src/main.rs",
+        r#"
+#[inline(never)]
+fn foo() {}
+
+fn main() {
+    foo(); // remark: expect-inlined
+}
+"#,
+    );
+    let output = cargo_remark(&project.dir, &["check", "--expectations"])?.assert_error();
+    assert!(output.stdout().contains("1 broken"));
+
+    project.file(
+        "src/main.rs",
+        r#"
+#[inline(never)]
+fn foo() {}
+
+fn main() {
+    // remark: expect inline
+    foo();
+}
+"#,
+    );
+    cargo_remark(&project.dir, &["check", "--expectations"])?.assert_ok();
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use crate::utils::{cargo_remark, get_test_data_path, HTMLDir, OutputExt};
+
+#[test]
+fn diff_same_directory() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let remark_dir = get_test_data_path("remarks-1");
+    let remark_dir = remark_dir.to_str().unwrap();
+    let output = cargo_remark(
+        dir.path(),
+        &[
+            "diff",
+            remark_dir,
+            remark_dir,
+            "--source-dir",
+            dir.path().to_str().unwrap(),
+            "--external",
+            "--format",
+            "json",
+        ],
+    )?
+    .assert_ok();
+
+    let diff: serde_json::Value = serde_json::from_str(&output.stdout())?;
+    assert_eq!(diff["schema_version"], 1);
+    for key in ["new", "fixed", "changed"] {
+        assert_eq!(diff[key].as_array().map(|remarks| remarks.len()), Some(0));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn diff_render_html() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let remark_dir = get_test_data_path("remarks-1");
+    let remark_dir = remark_dir.to_str().unwrap();
+    cargo_remark(
+        dir.path(),
+        &[
+            "diff",
+            remark_dir,
+            remark_dir,
+            "--source-dir",
+            dir.path().to_str().unwrap(),
+            "--format",
+            "html",
+            "--output-dir",
+            "out",
+        ],
+    )?
+    .assert_ok();
+    HTMLDir::new(&dir.path().join("out")).check_file("diff.html");
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::io::{BufReader, Write};
+use std::process::{ChildStdin, ChildStdout, Stdio};
+
+use lsp_server::{Message, Notification, Request, RequestId};
+use lsp_types::{Hover, HoverContents, PublishDiagnosticsParams};
+
+use crate::utils::{cargo_remark_command, get_test_data_path};
+
+#[test]
+fn lsp_diagnostics_and_hover() -> anyhow::Result<()> {
+    let data_dir = get_test_data_path("remarks-similarity-join");
+    let mut child = cargo_remark_command(
+        &data_dir,
+        &[
+            "lsp",
+            "--remark-dir",
+            data_dir.join("yaml").to_str().unwrap(),
+            "--source-dir",
+            data_dir.to_str().unwrap(),
+        ],
+    )
+    .stdin(Stdio::piped())
+    .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    send(
+        &mut stdin,
+        Request::new(
+            RequestId::from(1),
+            "initialize".to_string(),
+            serde_json::json!({ "capabilities": {} }),
+        ),
+    )?;
+    let Some(Message::Response(response)) = receive(&mut stdout)? else {
+        panic!("Expected initialize response");
+    };
+    assert_eq!(
+        response.result.unwrap()["capabilities"]["hoverProvider"],
+        true
+    );
+    send(
+        &mut stdin,
+        Notification::new("initialized".to_string(), serde_json::json!({})),
+    )?;
+
+    let Some(Message::Notification(notification)) = receive(&mut stdout)? else {
+        panic!("Expected diagnostics");
+    };
+    assert_eq!(notification.method, "textDocument/publishDiagnostics");
+    let diagnostics: PublishDiagnosticsParams = serde_json::from_value(notification.params)?;
+    let diagnostic = &diagnostics.diagnostics[0];
+    assert_eq!(diagnostic.source.as_deref(), Some("cargo-remark"));
+
+    send(
+        &mut stdin,
+        Request::new(
+            RequestId::from(2),
+            "textDocument/hover".to_string(),
+            serde_json::json!({
+                "textDocument": { "uri": diagnostics.uri },
+                "position": diagnostic.range.start,
+            }),
+        ),
+    )?;
+    let response = loop {
+        match receive(&mut stdout)? {
+            Some(Message::Response(response)) => break response,
+            Some(_) => continue,
+            None => panic!("Expected hover response"),
+        }
+    };
+    let hover: Hover = serde_json::from_value(response.result.unwrap())?;
+    let HoverContents::Markup(content) = hover.contents else {
+        panic!("Expected markup hover");
+    };
+    assert!(content.value.contains(&diagnostic.message[..10]));
+
+    send(
+        &mut stdin,
+        Request::new(RequestId::from(3), "shutdown".to_string(), ()),
+    )?;
+    send(
+        &mut stdin,
+        Notification::new("exit".to_string(), serde_json::json!(null)),
+    )?;
+    assert!(child.wait()?.success());
+
+    Ok(())
+}
+
+fn send<M: Into<Message>>(stdin: &mut ChildStdin, message: M) -> anyhow::Result<()> {
+    message.into().write(stdin)?;
+    stdin.flush()?;
+    Ok(())
+}
+
+fn receive(stdout: &mut BufReader<ChildStdout>) -> anyhow::Result<Option<Message>> {
+    Ok(Message::read(stdout)?)
+}
@@ -1,3 +1,6 @@
 mod analyze;
 mod build;
+mod check;
+mod diff;
+mod lsp;
 mod utils;
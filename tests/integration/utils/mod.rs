@@ -1,8 +1,15 @@
+use cargo_remark::build::toolchain::Toolchain;
+use cargo_remark::build::version::check_remark_dir_support;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
 use tempfile::TempDir;
 
 pub fn cargo_remark(dir: &Path, args: &[&str]) -> anyhow::Result<Output> {
+    let child = cargo_remark_command(dir, args).spawn()?;
+    Ok(child.wait_with_output()?)
+}
+
+pub fn cargo_remark_command(dir: &Path, args: &[&str]) -> Command {
     let mut command = Command::new("cargo");
     command.arg("remark");
     for arg in args {
@@ -16,9 +23,17 @@ pub fn cargo_remark(dir: &Path, args: &[&str]) -> anyhow::Result<Output> {
     let path = format!("{}:{}", get_target_dir().display(), path);
 
     command.env("PATH", path);
+    command
+}
 
-    let child = command.spawn()?;
-    Ok(child.wait_with_output()?)
+/// Returns true if the default compiler supports `-Zremark-dir`.
+/// Tests that check YAML remark files are skipped on compilers without it.
+pub fn remark_dir_supported() -> bool {
+    let supported = check_remark_dir_support(&Toolchain::default()).unwrap_or(false);
+    if !supported {
+        eprintln!("Skipping test, the compiler does not support `-Zremark-dir`");
+    }
+    supported
 }
 
 pub fn analyze_remarks(dir: &Path, args: &[&str]) -> anyhow::Result<Output> {
@@ -39,6 +54,29 @@ pub fn analyze_remarks(dir: &Path, args: &[&str]) -> anyhow::Result<Output> {
     Ok(child.wait_with_output()?)
 }
 
+/// Exports remarks from `remark_dir` as JSON lines with `analyze-remarks` and parses them.
+pub fn export_remarks_jsonl(
+    dir: &Path,
+    source_dir: &Path,
+    remark_dir: &Path,
+    args: &[&str],
+) -> anyhow::Result<Vec<serde_json::Value>> {
+    let mut arguments = vec![
+        "--source-dir",
+        source_dir.to_str().unwrap(),
+        "--format",
+        "jsonl",
+    ];
+    arguments.extend_from_slice(args);
+    arguments.push(remark_dir.to_str().unwrap());
+    let output = analyze_remarks(dir, &arguments)?.assert_ok();
+    Ok(output
+        .stdout()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect())
+}
+
 pub trait OutputExt {
     fn assert_ok(self) -> Self;
     fn assert_error(self) -> Self;
@@ -108,6 +146,10 @@ impl HTMLDir {
         self.check_exists(Path::new("src").join(file));
     }
 
+    pub fn check_file(&self, file: &str) {
+        self.check_exists(file);
+    }
+
     fn check_exists<P: AsRef<Path>>(&self, path: P) {
         let path = self.dir.join(path.as_ref());
         assert!(path.is_file());